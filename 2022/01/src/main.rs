@@ -1,11 +1,14 @@
+use records::records;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::fmt::Display;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Lines};
+use std::io::{BufRead, BufReader};
 use std::path::Path;
 
 const INPUT: &str = "input.txt";
 
-fn read_lines<N>(name: N) -> Lines<BufReader<File>>
+fn read_lines<N>(name: N) -> impl Iterator<Item = String>
 where
     N: AsRef<Path> + Display,
 {
@@ -13,29 +16,100 @@ where
         Ok(f) => f,
         Err(e) => panic!("open input error {e}"),
     };
-    BufReader::new(file).lines()
+    BufReader::new(file).lines().map(|x| x.unwrap())
+}
+
+// the calories each elf carries
+fn totals<I, S>(lines: I) -> impl Iterator<Item = u64>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    records(lines).map(|elf| {
+        elf.iter()
+            .map(|x| x.as_ref().trim().parse::<u64>().unwrap())
+            .sum()
+    })
+}
+
+// the k largest, largest first, holding no more than k at a time
+fn top_k(totals: impl Iterator<Item = u64>, k: usize) -> Vec<u64> {
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+    for t in totals {
+        heap.push(Reverse(t));
+        if heap.len() > k {
+            heap.pop();
+        }
+    }
+    heap.into_sorted_vec().into_iter().map(|x| x.0).collect()
+}
+
+fn process<I, S>(lines: I, k: usize) -> u64
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    top_k(totals(lines), k).iter().sum()
+}
+
+fn process1<I, S>(lines: I) -> u64
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    process(lines, 1)
+}
+
+fn process2<I, S>(lines: I) -> u64
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    process(lines, 3)
 }
 
 fn main() {
-    let lines = read_lines(INPUT);
-    let mut totals = Vec::new();
-    let mut total = 0;
-
-    for line in lines {
-        let line = line.unwrap();
-        if line != "" {
-            total += line.parse::<u64>().unwrap();
-        } else {
-            totals.push(total);
-            total = 0;
+    let total = process1(read_lines(INPUT));
+    println!("1: {total}");
+    let total = process2(read_lines(INPUT));
+    println!("2: {total}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n\
+                           7000\n8000\n9000\n\n10000";
+
+    #[test]
+    fn test_top_k() {
+        let t = totals(EXAMPLE.lines()).collect::<Vec<_>>();
+        assert_eq!(t, [6000, 4000, 11000, 24000, 10000]);
+        assert_eq!(top_k(t.iter().copied(), 3), [24000, 11000, 10000]);
+        assert_eq!(top_k(t.iter().copied(), 9).len(), 5);
+        assert_eq!(top_k(t.iter().copied(), 0), []);
+    }
+
+    #[test]
+    fn test_endings() {
+        let crlf = EXAMPLE.replace('\n', "\r\n") + "\r\n";
+        let padded = format!("\n{}\n\n\n", EXAMPLE.replace("\n\n", "\n\n\n"));
+        for input in [EXAMPLE, &crlf, &padded] {
+            assert_eq!(totals(input.lines()).count(), 5);
+            assert_eq!(process2(input.lines()), 45000);
         }
+        // the last elf counts without a blank line after it
+        assert_eq!(process1("1\n\n2\n3".lines()), 5);
+    }
+
+    #[test]
+    fn example1() {
+        assert_eq!(process1(EXAMPLE.lines()), 24000);
     }
 
-    totals.sort_by(|a, b| b.cmp(a));
-    let mut top3 = 0;
-    for i in 0..3 {
-        println!("{}", totals[i]);
-        top3 += totals[i];
+    #[test]
+    fn example2() {
+        assert_eq!(process2(EXAMPLE.lines()), 45000);
     }
-    println!("top 3 total: {top3}");
 }
@@ -1,3 +1,4 @@
+use std::env;
 use std::fmt::Display;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
@@ -50,30 +51,103 @@ impl FromStr for Outcome {
 }
 
 impl Choice {
-    fn battle(&self, x: &Choice) -> Outcome {
-        match (self, x) {
-            (Choice::Rock, Choice::Rock) => Outcome::Tie,
-            (Choice::Paper, Choice::Paper) => Outcome::Tie,
-            (Choice::Scissors, Choice::Scissors) => Outcome::Tie,
-            (Choice::Rock, Choice::Scissors) => Outcome::Loss,
-            (Choice::Scissors, Choice::Paper) => Outcome::Loss,
-            (Choice::Paper, Choice::Rock) => Outcome::Loss,
-            _ => Outcome::Win,
+    fn index(self) -> usize {
+        self as usize - 1
+    }
+}
+
+// moves 0..n of an odd-sized game go round in a circle: a move beats the
+// ones an odd number of steps behind it and loses to the rest, so each
+// move beats exactly half of the others.  with 5 moves the order rock,
+// paper, scissors, spock, lizard gives the usual rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Game {
+    moves: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct GameSizeError(usize);
+
+// lines count from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HistoryError {
+    Blank { line: usize },
+    Move { line: usize, c: char },
+}
+
+const RPS: Game = Game { moves: 3 };
+
+impl Game {
+    fn new(moves: usize) -> Result<Game, GameSizeError> {
+        if moves < 3 || moves.is_multiple_of(2) {
+            return Err(GameSizeError(moves));
+        }
+        Ok(Game { moves })
+    }
+
+    fn outcome(&self, me: usize, them: usize) -> Outcome {
+        match (me + self.moves - them) % self.moves {
+            0 => Outcome::Tie,
+            d if d % 2 == 1 => Outcome::Win,
+            _ => Outcome::Loss,
         }
     }
 
-    fn from_outcome(&self, x: &Outcome) -> Choice {
-        match (self, x) {
-            (Choice::Rock, Outcome::Tie) => Choice::Rock,
-            (Choice::Paper, Outcome::Tie) => Choice::Paper,
-            (Choice::Scissors, Outcome::Tie) => Choice::Scissors,
-            (Choice::Rock, Outcome::Win) => Choice::Paper,
-            (Choice::Paper, Outcome::Win) => Choice::Scissors,
-            (Choice::Scissors, Outcome::Win) => Choice::Rock,
-            (Choice::Rock, Outcome::Loss) => Choice::Scissors,
-            (Choice::Paper, Outcome::Loss) => Choice::Rock,
-            (Choice::Scissors, Outcome::Loss) => Choice::Paper,
+    // a move giving `want` against `them`
+    fn respond(&self, them: usize, want: Outcome) -> usize {
+        let d = match want {
+            Outcome::Tie => 0,
+            Outcome::Win => 1,
+            Outcome::Loss => 2,
+        };
+        (them + d) % self.moves
+    }
+
+    // a move scores one more than its index
+    fn score(&self, me: usize, them: usize) -> u64 {
+        (me + 1) as u64 + self.outcome(me, them) as u64
+    }
+
+    // strategies hold the probability of playing each move
+    fn expected(&self, ours: &[f64], theirs: &[f64]) -> f64 {
+        let mut total = 0.0;
+        for (me, p) in ours.iter().enumerate() {
+            for (them, q) in theirs.iter().enumerate() {
+                total += p * q * self.score(me, them) as f64;
+            }
         }
+        total
+    }
+
+    fn pure(&self, me: usize) -> Vec<f64> {
+        let mut s = vec![0.0; self.moves];
+        s[me] = 1.0;
+        s
+    }
+
+    // how often the opponent played each move, if they played at all
+    fn frequencies(&self, history: &[usize]) -> Option<Vec<f64>> {
+        if history.is_empty() {
+            return None;
+        }
+        let mut s = vec![0.0; self.moves];
+        for m in history {
+            s[*m] += 1.0;
+        }
+        Some(s.iter().map(|x| x / history.len() as f64).collect())
+    }
+
+    // the move with the best expected score against `theirs`, lowest first
+    // on ties, and that score
+    fn best_response(&self, theirs: &[f64]) -> (usize, f64) {
+        let mut best = (0, f64::MIN);
+        for me in 0..self.moves {
+            let e = self.expected(&self.pure(me), theirs);
+            if e > best.1 {
+                best = (me, e);
+            }
+        }
+        best
     }
 }
 
@@ -93,28 +167,76 @@ fn process(first: bool, lines: Vec<String>) -> u64 {
 
     for line in lines {
         let parts = line.split(' ').collect::<Vec<_>>();
-        let opponent = parts[0].parse::<Choice>().unwrap();
-        let me;
-
-        if first {
-            me = parts[1].parse::<Choice>().unwrap();
+        let opponent = parts[0].parse::<Choice>().unwrap().index();
+        let me = if first {
+            parts[1].parse::<Choice>().unwrap().index()
         } else {
             let outcome = parts[1].parse::<Outcome>().unwrap();
-            me = opponent.from_outcome(&outcome);
-        }
+            RPS.respond(opponent, outcome)
+        };
 
-        total += me as u64 + opponent.battle(&me) as u64;
+        total += RPS.score(me, opponent);
     }
 
     total
 }
 
+// the opponent's column, where A, B, C and on are moves 0, 1, 2 and on
+fn history(game: Game, lines: &[String]) -> Result<Vec<usize>, HistoryError> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, l)| {
+            let line = i + 1;
+            let c = l.chars().next().ok_or(HistoryError::Blank { line })?;
+            (c as usize)
+                .checked_sub('A' as usize)
+                .filter(|m| *m < game.moves)
+                .ok_or(HistoryError::Move { line, c })
+        })
+        .collect()
+}
+
 fn process_and_print(first: bool, num: u8, input: &str) {
     let total = process(first, read_lines(input));
     println!("{num}: {total}");
 }
 
-fn main () {
+fn analyze(game: Game, input: &str) {
+    let history = match history(game, &read_lines(input)) {
+        Ok(history) => history,
+        Err(e) => return println!("{e:?}"),
+    };
+    let Some(theirs) = game.frequencies(&history) else {
+        return println!("no rounds to analyze");
+    };
+    println!("opponent: {theirs:?}");
+    for me in 0..game.moves {
+        let e = game.expected(&game.pure(me), &theirs);
+        println!("always {me}: {e:.3} a round");
+    }
+    let uniform = vec![1.0 / game.moves as f64; game.moves];
+    let e = game.expected(&uniform, &theirs);
+    println!("uniform: {e:.3} a round");
+    let (me, e) = game.best_response(&theirs);
+    let total = e * history.len() as f64;
+    println!("best: always {me}, {e:.3} a round, {total:.0} in all");
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.first().map(|x| x.as_str()) == Some("analyze") {
+        let moves = match args.get(1).map(|n| n.parse()) {
+            None => 3,
+            Some(Ok(n)) => n,
+            Some(Err(_)) => return println!("usage: day02 [analyze [MOVES]]"),
+        };
+        match Game::new(moves) {
+            Ok(game) => analyze(game, INPUT),
+            Err(e) => println!("{e:?}"),
+        }
+        return;
+    }
     process_and_print(true, 1, INPUT);
     process_and_print(false, 2, INPUT);
 }
@@ -128,7 +250,7 @@ mod tests {
                            C Z\n";
 
     fn test(first: bool, lines: &str, expect: u64) {
-        let lines = lines.lines().map(|x| String::from(x)).collect();
+        let lines = lines.lines().map(String::from).collect();
         let total = process(first, lines);
         assert_eq!(total, expect);
     }
@@ -142,4 +264,72 @@ mod tests {
     fn example2() {
         test(false, EXAMPLE, 12);
     }
+
+    #[test]
+    fn test_game() {
+        for me in 0..3 {
+            for them in 0..3 {
+                for want in [Outcome::Loss, Outcome::Tie, Outcome::Win] {
+                    let r = RPS.respond(them, want);
+                    assert_eq!(RPS.outcome(r, them), want);
+                }
+                if me != them {
+                    let a = RPS.outcome(me, them);
+                    assert_ne!(a, RPS.outcome(them, me));
+                }
+            }
+        }
+        let (rock, paper, scissors) = (0, 1, 2);
+        assert_eq!(RPS.outcome(paper, rock), Outcome::Win);
+        assert_eq!(RPS.outcome(rock, paper), Outcome::Loss);
+        assert_eq!(RPS.outcome(rock, scissors), Outcome::Win);
+
+        let rpsls = Game::new(5).unwrap();
+        let (spock, lizard) = (3, 4);
+        assert_eq!(rpsls.outcome(spock, scissors), Outcome::Win);
+        assert_eq!(rpsls.outcome(spock, rock), Outcome::Win);
+        assert_eq!(rpsls.outcome(lizard, spock), Outcome::Win);
+        assert_eq!(rpsls.outcome(lizard, paper), Outcome::Win);
+        assert_eq!(rpsls.outcome(rock, lizard), Outcome::Win);
+        assert_eq!(rpsls.outcome(scissors, lizard), Outcome::Win);
+        assert_eq!(rpsls.outcome(paper, spock), Outcome::Win);
+        assert_eq!(rpsls.outcome(lizard, scissors), Outcome::Loss);
+        for me in 0..5 {
+            let wins = (0..5).filter(|t| rpsls.outcome(me, *t) == Outcome::Win);
+            assert_eq!(wins.count(), 2);
+        }
+
+        assert_eq!(Game::new(4), Err(GameSizeError(4)));
+        assert_eq!(Game::new(1), Err(GameSizeError(1)));
+    }
+
+    #[test]
+    fn test_strategy() {
+        let lines = EXAMPLE.lines().map(String::from).collect::<Vec<_>>();
+        assert_eq!(history(RPS, &lines), Ok(vec![0, 1, 2]));
+        let lines = ["A Y", "", "C Z"].map(String::from);
+        assert_eq!(history(RPS, &lines), Err(HistoryError::Blank { line: 2 }));
+        let lines = ["A Y", "D X"].map(String::from);
+        let bad = HistoryError::Move { line: 2, c: 'D' };
+        assert_eq!(history(RPS, &lines), Err(bad));
+        assert_eq!(history(Game::new(5).unwrap(), &lines), Ok(vec![0, 3]));
+        let lines = ["a Y"].map(String::from);
+        let bad = HistoryError::Move { line: 1, c: 'a' };
+        assert_eq!(history(RPS, &lines), Err(bad));
+        assert_eq!(history(RPS, &[]), Ok(vec![]));
+        assert_eq!(RPS.frequencies(&[]), None);
+
+        let theirs = RPS.frequencies(&[0, 1, 2, 0]).unwrap();
+        assert_eq!(theirs, [0.5, 0.25, 0.25]);
+        assert_eq!(RPS.expected(&RPS.pure(0), &theirs), 4.0);
+        assert_eq!(RPS.expected(&RPS.pure(2), &theirs), 5.25);
+        assert_eq!(RPS.best_response(&theirs), (1, 5.75));
+
+        let theirs = RPS.frequencies(&[0, 0, 0, 1]).unwrap();
+        assert_eq!(theirs, [0.75, 0.25, 0.0]);
+        assert_eq!(RPS.best_response(&theirs), (1, 2.0 + 0.75 * 6.0 + 0.75));
+        let mixed = [0.5, 0.5, 0.0];
+        let e = RPS.expected(&mixed, &theirs);
+        assert_eq!(e, 0.5 * (1.0 + 0.75 * 3.0) + 0.5 * (2.0 + 4.5 + 0.75));
+    }
 }
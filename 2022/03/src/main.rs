@@ -1,23 +1,56 @@
-use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 
-fn get_map() -> HashMap<char, u64> {
-    let mut x = 1;
-    let mut h = HashMap::new();
+// one bit per item type, bit (priority - 1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Items(u64);
 
-    for c in 'a'..='z' {
-        h.insert(c, x);
-        x += 1;
+// lines and groups count from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SackError {
+    UnknownItem { line: usize, item: char },
+    OddLength { line: usize },
+    NoneShared { group: usize },
+    ManyShared { group: usize, items: Items },
+    ShortGroup { group: usize, len: usize },
+    GroupSize,
+}
+
+fn priority(c: u8) -> Option<u32> {
+    match c {
+        b'a'..=b'z' => Some((c - b'a') as u32 + 1),
+        b'A'..=b'Z' => Some((c - b'A') as u32 + 27),
+        _ => None,
     }
-    for c in 'A'..='Z' {
-        h.insert(c, x);
-        x += 1;
+}
+
+impl Items {
+    fn parse(s: &[u8], line: usize) -> Result<Items, SackError> {
+        s.iter().try_fold(Items(0), |items, c| match priority(*c) {
+            Some(p) => Ok(Items(items.0 | 1 << (p - 1))),
+            None => Err(SackError::UnknownItem {
+                line,
+                item: *c as char,
+            }),
+        })
+    }
+
+    fn common(&self, other: Items) -> Items {
+        Items(self.0 & other.0)
     }
 
-    h
+    fn only(&self, group: usize) -> Result<u32, SackError> {
+        match self.0.count_ones() {
+            0 => Err(SackError::NoneShared { group }),
+            1 => Ok(self.0.trailing_zeros() + 1),
+            _ => Err(SackError::ManyShared {
+                group,
+                items: *self,
+            }),
+        }
+    }
 }
 
 fn read_lines<N>(name: N) -> Vec<String>
@@ -31,53 +64,57 @@ where
     BufReader::new(file).lines().map(|x| x.unwrap()).collect()
 }
 
-fn process1(lines: &Vec<String>) -> u64 {
-    let map = get_map();
+fn process1(lines: &[String]) -> Result<u64, SackError> {
+    lines.iter().enumerate().try_fold(0, |total, (i, x)| {
+        let i = i + 1;
+        let x = x.as_bytes();
+        if x.len() % 2 != 0 {
+            return Err(SackError::OddLength { line: i });
+        }
+        let (a, b) = x.split_at(x.len() / 2);
+        let shared = Items::parse(a, i)?.common(Items::parse(b, i)?);
+        Ok(total + shared.only(i)? as u64)
+    })
+}
+
+fn badges(lines: &[String], size: usize) -> Result<u64, SackError> {
+    if size == 0 {
+        return Err(SackError::GroupSize);
+    }
     lines
-        .iter()
-        .map(|x| {
-            assert_eq!(x.len() % 2, 0);
-            vec![x[0..x.len() / 2].to_string(), x[x.len() / 2..].to_string()]
-        })
-        .map(|x| {
-            x.iter()
-                .map(|y| y.chars().collect::<HashSet<_>>())
-                .collect::<Vec<_>>()
+        .chunks(size)
+        .enumerate()
+        .try_fold(0, |total, (g, group)| {
+            if group.len() < size {
+                return Err(SackError::ShortGroup {
+                    group: g + 1,
+                    len: group.len(),
+                });
+            }
+            let mut shared = Items(u64::MAX);
+            for (i, x) in group.iter().enumerate() {
+                let line = g * size + i + 1;
+                shared = shared.common(Items::parse(x.as_bytes(), line)?);
+            }
+            Ok(total + shared.only(g + 1)? as u64)
         })
-        .collect::<Vec<_>>()
-        .iter()
-        .map(|x| x[0].intersection(&x[1]).collect::<Vec<_>>())
-        .flatten()
-        .map(|x| map.get(x).unwrap())
-        .sum()
 }
 
-fn process2(lines: &Vec<String>) -> u64 {
-    let map = get_map();
-    let mut total = 0;
-    let mut bags = lines.iter().map(|x| x.chars().collect::<HashSet<_>>());
-
-    loop {
-        let e0 = bags.next();
-        let e1 = bags.next();
-        let e2 = bags.next();
-        let (mut e0, e1, e2) = match (e0, e1, e2) {
-            (Some(e0), Some(e1), Some(e2)) => (e0, e1, e2),
-            _ => break total,
-        };
-        e0.retain(|x| e1.contains(x) && e2.contains(x));
-        let badge = e0.iter().collect::<Vec<_>>()[0];
-        total += map.get(badge).unwrap();
-    }
+fn process2(lines: &[String]) -> Result<u64, SackError> {
+    badges(lines, 3)
 }
 
 fn main() {
     const INPUT: &str = "input.txt";
     let lines = read_lines(INPUT);
-    let total = process1(&lines);
-    println!("1: {total}");
-    let total = process2(&lines);
-    println!("2: {total}");
+    match process1(&lines) {
+        Ok(total) => println!("1: {total}"),
+        Err(e) => println!("1: {e:?}"),
+    }
+    match process2(&lines) {
+        Ok(total) => println!("2: {total}"),
+        Err(e) => println!("2: {e:?}"),
+    }
 }
 
 #[cfg(test)]
@@ -92,10 +129,14 @@ mod tests {
     ttgJtRGJQctTZtZT\n\
     CrZsJsPPZsGzwwsLwLmpwMDw";
 
-    fn test(t: fn(&Vec<String>) -> u64, expected: u64) {
-        let lines = EXAMPLE.lines().map(|x| String::from(x)).collect();
+    fn const_to_lines(c: &str) -> Vec<String> {
+        c.lines().map(String::from).collect()
+    }
+
+    fn test(t: fn(&[String]) -> Result<u64, SackError>, expected: u64) {
+        let lines = const_to_lines(EXAMPLE);
         let actual = t(&lines);
-        assert_eq!(actual, expected);
+        assert_eq!(actual, Ok(expected));
     }
 
     #[test]
@@ -107,4 +148,45 @@ mod tests {
     fn example2() {
         test(process2, 70);
     }
+
+    #[test]
+    fn test_priority() {
+        assert_eq!(priority(b'a'), Some(1));
+        assert_eq!(priority(b'z'), Some(26));
+        assert_eq!(priority(b'A'), Some(27));
+        assert_eq!(priority(b'Z'), Some(52));
+        assert_eq!(priority(b'!'), None);
+    }
+
+    #[test]
+    fn test_group_sizes() {
+        let lines = const_to_lines("abX\ncdX\nefX\nghX");
+        assert_eq!(badges(&lines, 2), Ok(100));
+        assert_eq!(badges(&lines, 4), Ok(50));
+        assert_eq!(
+            badges(&lines, 3),
+            Err(SackError::ShortGroup { group: 2, len: 1 })
+        );
+        assert_eq!(badges(&lines, 0), Err(SackError::GroupSize));
+        assert_eq!(badges(&[], 0), Err(SackError::GroupSize));
+    }
+
+    #[test]
+    fn test_errors() {
+        let lines = const_to_lines("abc\nabd\nxyz");
+        assert_eq!(badges(&lines, 3), Err(SackError::NoneShared { group: 1 }));
+        assert_eq!(
+            badges(&lines[..2], 2),
+            Err(SackError::ManyShared {
+                group: 1,
+                items: Items(0b11)
+            })
+        );
+        assert_eq!(process1(&lines), Err(SackError::OddLength { line: 1 }));
+        let lines = const_to_lines("ab1b");
+        assert_eq!(
+            process1(&lines),
+            Err(SackError::UnknownItem { line: 1, item: '1' })
+        );
+    }
 }
@@ -1,8 +1,63 @@
-use std::collections::HashSet;
+use std::cmp::max;
 use std::fmt::Display;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Range {
+    lo: u64,
+    hi: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ParseRangeError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Containment {
+    Equal,
+    FirstHoldsSecond,
+    SecondHoldsFirst,
+}
+
+impl FromStr for Range {
+    type Err = ParseRangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (lo, hi) = s.split_once('-').ok_or(ParseRangeError)?;
+        let lo = lo.parse().map_err(|_| ParseRangeError)?;
+        let hi = hi.parse().map_err(|_| ParseRangeError)?;
+        if lo > hi {
+            return Err(ParseRangeError);
+        }
+        Ok(Range { lo, hi })
+    }
+}
+
+impl Range {
+    fn contains(&self, other: &Range) -> bool {
+        self.lo <= other.lo && other.hi <= self.hi
+    }
+
+    fn overlaps(&self, other: &Range) -> bool {
+        self.lo <= other.hi && other.lo <= self.hi
+    }
+
+    // 0-18446744073709551615 holds 2^64 sections, one past u64
+    fn len(&self) -> u128 {
+        (self.hi - self.lo) as u128 + 1
+    }
+}
+
+fn containment(a: &Range, b: &Range) -> Option<Containment> {
+    match (a.contains(b), b.contains(a)) {
+        (true, true) => Some(Containment::Equal),
+        (true, false) => Some(Containment::FirstHoldsSecond),
+        (false, true) => Some(Containment::SecondHoldsFirst),
+        (false, false) => None,
+    }
+}
 
 fn read_lines<N>(name: N) -> Vec<String>
 where
@@ -15,41 +70,79 @@ where
     BufReader::new(file).lines().map(|x| x.unwrap()).collect()
 }
 
-fn make_range(x: u64, y: u64) -> HashSet<u64> {
-    (x..=y).collect::<HashSet<_>>()
-}
-
-fn transform_ranges<T>(r: &Vec<&str>, f: fn(u64, u64) -> T) -> Vec<T> {
-    r.iter()
+fn parse(lines: &[String]) -> Vec<(Range, Range)> {
+    lines
+        .iter()
         .map(|x| {
-            let x = x.split("-").collect::<Vec<_>>();
-            let (x, y) =
-                (x[0].parse::<u64>().unwrap(), x[1].parse::<u64>().unwrap());
-            f(x, y)
+            let (a, b) = x.split_once(',').unwrap();
+            (a.parse().unwrap(), b.parse().unwrap())
         })
-        .collect::<Vec<_>>()
+        .collect()
 }
 
-fn process1(lines: &Vec<String>) -> usize {
-    lines
+fn process1(lines: &[String]) -> usize {
+    parse(lines)
         .iter()
-        .map(|x| x.split(",").collect::<Vec<_>>())
-        .map(|x| transform_ranges(&x, make_range))
-        .filter(|x| x[0].is_subset(&x[1]) || x[1].is_subset(&x[0]))
+        .filter(|(a, b)| a.contains(b) || b.contains(a))
         .count()
 }
 
-fn overlap(x: (u64, u64), y: (u64, u64)) -> bool {
-    x.0 <= y.1 && y.0 <= x.1
+fn process2(lines: &[String]) -> usize {
+    parse(lines).iter().filter(|(a, b)| a.overlaps(b)).count()
 }
 
-fn process2(lines: &Vec<String>) -> usize {
-    lines
+fn covered(pairs: &[(Range, Range)]) -> u128 {
+    let mut ranges =
+        pairs.iter().flat_map(|(a, b)| [*a, *b]).collect::<Vec<_>>();
+    ranges.sort();
+
+    let mut total = 0;
+    let mut cur: Option<Range> = None;
+    for r in ranges {
+        cur = match cur {
+            Some(c) if r.lo <= c.hi.saturating_add(1) => Some(Range {
+                lo: c.lo,
+                hi: max(c.hi, r.hi),
+            }),
+            Some(c) => {
+                total += c.len();
+                Some(r)
+            }
+            None => Some(r),
+        };
+    }
+    total + cur.map_or(0, |c| c.len())
+}
+
+// lowest section with the most elves assigned, and how many
+fn busiest(pairs: &[(Range, Range)]) -> Option<(u64, usize)> {
+    let mut events = pairs
         .iter()
-        .map(|x| x.split(",").collect::<Vec<_>>())
-        .map(|x| transform_ranges(&x, |y, z| (y, z)))
-        .filter(|x| overlap(x[0], x[1]))
-        .count()
+        .flat_map(|(a, b)| [*a, *b])
+        .flat_map(|r| [(r.lo as u128, 1), (r.hi as u128 + 1, -1)])
+        .collect::<Vec<(u128, i64)>>();
+    // ends go one past the range, in u128 to leave room above u64::MAX,
+    // and sort before starts at the same section
+    events.sort();
+
+    let mut best = None;
+    let mut depth = 0;
+    for (section, delta) in events {
+        depth += delta;
+        if delta > 0 && best.is_none_or(|(_, d)| depth > d) {
+            best = Some((section, depth));
+        }
+    }
+    // only starts are kept, and those fit in u64
+    best.map(|(s, d)| (s as u64, d as usize))
+}
+
+fn contained(pairs: &[(Range, Range)]) -> Vec<(usize, Containment)> {
+    pairs
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (a, b))| containment(a, b).map(|c| (i, c)))
+        .collect()
 }
 
 fn main() {
@@ -59,6 +152,26 @@ fn main() {
     println!("1: {total}");
     let total = process2(&lines);
     println!("2: {total}");
+
+    let pairs = parse(&lines);
+    println!("covered: {}", covered(&pairs));
+    if let Some((section, elves)) = busiest(&pairs) {
+        println!("busiest: section {section} with {elves} elves");
+    }
+    for (i, c) in contained(&pairs) {
+        let (a, b) = pairs[i];
+        let (a, b) =
+            (format!("{}-{}", a.lo, a.hi), format!("{}-{}", b.lo, b.hi));
+        match c {
+            Containment::Equal => println!("{}: {a} equals {b}", i + 1),
+            Containment::FirstHoldsSecond => {
+                println!("{}: {a} contains {b}", i + 1)
+            }
+            Containment::SecondHoldsFirst => {
+                println!("{}: {b} contains {a}", i + 1)
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -73,8 +186,12 @@ mod tests {
     6-6,4-6\n\
     2-6,4-8";
 
-    fn test(t: fn(&Vec<String>) -> usize, expected: usize) {
-        let lines = EXAMPLE.lines().map(|x| String::from(x)).collect();
+    fn const_to_lines(c: &str) -> Vec<String> {
+        c.lines().map(String::from).collect()
+    }
+
+    fn test(t: fn(&[String]) -> usize, expected: usize) {
+        let lines = const_to_lines(EXAMPLE);
         let actual = t(&lines);
         assert_eq!(actual, expected);
     }
@@ -88,4 +205,48 @@ mod tests {
     fn example2() {
         test(process2, 4);
     }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("2-4".parse(), Ok(Range { lo: 2, hi: 4 }));
+        assert_eq!("4-2".parse::<Range>(), Err(ParseRangeError));
+        assert_eq!("4".parse::<Range>(), Err(ParseRangeError));
+    }
+
+    #[test]
+    fn test_analytics() {
+        let pairs = parse(&const_to_lines(EXAMPLE));
+        assert_eq!(covered(&pairs), 8);
+        assert_eq!(busiest(&pairs), Some((6, 8)));
+        assert_eq!(
+            contained(&pairs),
+            vec![
+                (3, Containment::FirstHoldsSecond),
+                (4, Containment::SecondHoldsFirst)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_huge() {
+        let lines = const_to_lines("1-4000000000,2-3999999999\n5-5,7-9");
+        let pairs = parse(&lines);
+        assert_eq!(process1(&lines), 1);
+        assert_eq!(covered(&pairs), 4000000000);
+        assert_eq!(busiest(&pairs), Some((5, 3)));
+    }
+
+    #[test]
+    fn test_max() {
+        let lines = const_to_lines("0-18446744073709551615,1-2\n5-9,9-9");
+        let pairs = parse(&lines);
+        assert_eq!(process1(&lines), 2);
+        assert_eq!(process2(&lines), 2);
+        assert_eq!(covered(&pairs), 1 << 64);
+        assert_eq!(busiest(&pairs), Some((9, 3)));
+        let top =
+            const_to_lines("18446744073709551615-18446744073709551615,1-1");
+        assert_eq!(covered(&parse(&top)), 2);
+        assert_eq!(busiest(&parse(&top)), Some((1, 1)));
+    }
 }
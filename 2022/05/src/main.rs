@@ -1,3 +1,6 @@
+use records::records;
+use std::cmp::max;
+use std::env;
 use std::fmt::Display;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
@@ -5,6 +8,7 @@ use std::path::Path;
 
 type Stacks = Vec<Vec<char>>;
 
+// from and to are stack labels, counting from 1
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Move {
     num: usize,
@@ -12,6 +16,141 @@ struct Move {
     to: usize,
 }
 
+// rows and columns of the drawing count from 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Position {
+    row: usize,
+    col: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DrawingError {
+    NoLabels,
+    BadLabel { col: usize },
+    Malformed(Position),
+    Unlabeled(Position),
+    Floating(Position),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MoveFault {
+    NoStack(usize),
+    Short { stack: usize, has: usize },
+    NoLift,
+}
+
+// index counts moves from 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MoveError {
+    index: usize,
+    fault: MoveFault,
+}
+
+// lines of the file count from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputError {
+    Drawing(DrawingError),
+    BadMove { line: usize },
+    Move(MoveError),
+}
+
+impl From<DrawingError> for InputError {
+    fn from(e: DrawingError) -> Self {
+        InputError::Drawing(e)
+    }
+}
+
+impl From<MoveError> for InputError {
+    fn from(e: MoveError) -> Self {
+        InputError::Move(e)
+    }
+}
+
+trait Crane {
+    fn apply(&self, stacks: &mut Stacks, m: Move) -> Result<(), MoveFault>;
+
+    fn run(
+        &self, stacks: &mut Stacks, moves: &[Move],
+    ) -> Result<(), MoveError> {
+        for (index, m) in moves.iter().enumerate() {
+            self.apply(stacks, *m)
+                .map_err(|fault| MoveError { index, fault })?;
+        }
+        Ok(())
+    }
+}
+
+// moves one crate at a time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CrateMover9000;
+
+// moves all the crates at once, keeping their order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CrateMover9001;
+
+// moves at most `lift` crates at a time with another crane
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Limited<C: Crane> {
+    crane: C,
+    lift: usize,
+}
+
+fn stack(stacks: &Stacks, label: usize) -> Result<usize, MoveFault> {
+    match label.checked_sub(1) {
+        Some(i) if i < stacks.len() => Ok(i),
+        _ => Err(MoveFault::NoStack(label)),
+    }
+}
+
+// the top `m.num` crates of the source stack, bottom first, once both
+// stacks are known to exist
+fn take(stacks: &mut Stacks, m: Move) -> Result<Vec<char>, MoveFault> {
+    let from = stack(stacks, m.from)?;
+    stack(stacks, m.to)?;
+    let has = stacks[from].len();
+    if has < m.num {
+        return Err(MoveFault::Short { stack: m.from, has });
+    }
+    Ok(stacks[from].split_off(has - m.num))
+}
+
+impl Crane for CrateMover9000 {
+    fn apply(&self, stacks: &mut Stacks, m: Move) -> Result<(), MoveFault> {
+        let crates = take(stacks, m)?;
+        stacks[m.to - 1].extend(crates.into_iter().rev());
+        Ok(())
+    }
+}
+
+impl Crane for CrateMover9001 {
+    fn apply(&self, stacks: &mut Stacks, m: Move) -> Result<(), MoveFault> {
+        let crates = take(stacks, m)?;
+        stacks[m.to - 1].extend(crates);
+        Ok(())
+    }
+}
+
+impl<C: Crane> Crane for Limited<C> {
+    fn apply(&self, stacks: &mut Stacks, m: Move) -> Result<(), MoveFault> {
+        // check the whole move first so a failure leaves the stacks alone
+        if self.lift == 0 {
+            return Err(MoveFault::NoLift);
+        }
+        let has = stacks[stack(stacks, m.from)?].len();
+        stack(stacks, m.to)?;
+        if has < m.num {
+            return Err(MoveFault::Short { stack: m.from, has });
+        }
+        let mut left = m.num;
+        while left > 0 {
+            let num = left.min(self.lift);
+            self.crane.apply(stacks, Move { num, ..m })?;
+            left -= num;
+        }
+        Ok(())
+    }
+}
+
 fn read_lines<N>(name: N) -> Vec<String>
 where
     N: AsRef<Path> + Display,
@@ -23,74 +162,123 @@ where
     BufReader::new(file).lines().map(|x| x.unwrap()).collect()
 }
 
-fn parse_stacks(lines: &[String]) -> Stacks {
-    let numbers = &lines[lines.len() - 1];
-    let lines = lines
-        .iter()
-        .map(|x| x.chars().collect::<Vec<_>>())
-        .collect::<Vec<_>>();
-    let num = numbers.split_whitespace().count();
-    let mut stacks = Vec::new();
-
-    for _s in 0..num {
-        stacks.push(Vec::new());
+// the label of each stack and the columns it covers
+fn parse_labels(line: &str) -> Result<Vec<(usize, usize)>, DrawingError> {
+    let mut spans = Vec::new();
+    let mut start = None;
+    for (col, c) in line.chars().chain([' ']).enumerate() {
+        match (c.is_whitespace(), start) {
+            (true, Some(s)) => {
+                spans.push((s, col));
+                start = None;
+            }
+            (false, None) => start = Some(col),
+            _ => (),
+        }
+    }
+    if spans.is_empty() {
+        return Err(DrawingError::NoLabels);
+    }
+    let chars = line.chars().collect::<Vec<_>>();
+    for (n, (s, e)) in spans.iter().enumerate() {
+        let label = chars[*s..*e].iter().collect::<String>();
+        if label.parse() != Ok(n + 1) {
+            return Err(DrawingError::BadLabel { col: *s });
+        }
     }
-    for s in 0..num {
-        for h in (0..lines.len() - 1).rev() {
-            let c = lines[h][4 * s + 1];
-            if c != ' ' {
-                stacks[s].push(c);
+    Ok(spans)
+}
+
+// lines may stop short of the last stack; a crate belongs to the stack
+// whose label shares a column with its brackets
+fn parse_stacks<S: AsRef<str>>(lines: &[S]) -> Result<Stacks, DrawingError> {
+    let (labels, rows) = lines.split_last().ok_or(DrawingError::NoLabels)?;
+    let spans = parse_labels(labels.as_ref())?;
+    let mut stacks = vec![Vec::new(); spans.len()];
+
+    for (row, line) in rows.iter().enumerate().rev() {
+        let height = rows.len() - 1 - row;
+        let chars = line.as_ref().chars().collect::<Vec<_>>();
+        let mut col = 0;
+        while col < chars.len() {
+            if chars[col] == ' ' {
+                col += 1;
+                continue;
+            }
+            let at = Position { row, col };
+            if chars
+                .get(col..col + 3)
+                .is_none_or(|c| c[0] != '[' || c[2] != ']')
+            {
+                return Err(DrawingError::Malformed(at));
             }
+            let s = spans
+                .iter()
+                .position(|(a, b)| col < *b && *a < col + 3)
+                .ok_or(DrawingError::Unlabeled(at))?;
+            if stacks[s].len() != height {
+                return Err(DrawingError::Floating(at));
+            }
+            stacks[s].push(chars[col + 1]);
+            col += 3;
         }
     }
-    stacks
+    Ok(stacks)
+}
+
+// draws crates as [X] in cells wide enough for the longest label
+fn render(stacks: &Stacks) -> String {
+    let w = max(3, stacks.len().to_string().len() + 1);
+    let height = stacks.iter().map(Vec::len).max().unwrap_or(0);
+    let mut out = String::new();
+    for h in (0..height).rev() {
+        let cells = stacks.iter().map(|s| match s.get(h) {
+            Some(c) => format!("{:w$}", format!("[{c}]")),
+            None => " ".repeat(w),
+        });
+        out += &cells.collect::<Vec<_>>().join(" ");
+        out.push('\n');
+    }
+    let labels = (1..=stacks.len()).map(|n| format!(" {n:<0$}", w - 1));
+    out += &labels.collect::<Vec<_>>().join(" ");
+    out.push('\n');
+    out
 }
 
-fn parse_moves(lines: &[String]) -> Vec<Move> {
-    let lines = lines
-        .iter()
-        .map(|x| x.split(" ").collect::<Vec<_>>())
-        .collect::<Vec<_>>();
+// lines of "move NUM from FROM to TO", each with its line number
+fn parse_moves<S: AsRef<str>>(
+    lines: &[(usize, S)],
+) -> Result<Vec<Move>, InputError> {
     let mut moves = Vec::new();
-    for m in lines {
+    for (line, m) in lines {
+        let bad = InputError::BadMove { line: *line };
+        let m = m.as_ref().split_whitespace().collect::<Vec<_>>();
+        let ["move", num, "from", from, "to", to] = m[..] else {
+            return Err(bad);
+        };
+        let n = |x: &str| x.parse().or(Err(bad));
         moves.push(Move {
-            num: m[1].parse().unwrap(),
-            from: m[3].parse::<usize>().unwrap() - 1,
-            to: m[5].parse::<usize>().unwrap() - 1,
+            num: n(num)?,
+            from: n(from)?,
+            to: n(to)?,
         })
     }
-    moves
+    Ok(moves)
 }
 
-fn split_input(lines: &mut Vec<String>) -> (&[String], &[String]) {
-    let split = lines.iter_mut().position(|x| x == "").unwrap();
-    (&lines[..split], &lines[split + 1..])
+// the drawing, then every line after it that isn't blank, numbered from 1
+fn split_input(lines: &[String]) -> (Vec<&String>, Vec<(usize, &String)>) {
+    let stacks = records(lines).next().unwrap_or_default();
+    let blank = |l: &String| l.trim().is_empty();
+    let start = lines.iter().take_while(|l| blank(l)).count() + stacks.len();
+    let moves = lines.iter().enumerate().skip(start);
+    let moves = moves.filter(|(_, l)| !blank(l)).map(|(i, l)| (i + 1, l));
+    (stacks, moves.collect())
 }
 
-fn parse_input(lines: &mut Vec<String>) -> (Stacks, Vec<Move>) {
+fn parse_input(lines: &[String]) -> Result<(Stacks, Vec<Move>), InputError> {
     let (stacks, moves) = split_input(lines);
-    (parse_stacks(&stacks), parse_moves(&moves))
-}
-
-fn execute_moves(stacks: &mut Stacks, moves: Vec<Move>, advanced: bool) {
-    for m in moves {
-        if !advanced {
-            for _i in 0..m.num {
-                let x = stacks[m.from].pop().unwrap();
-                stacks[m.to].push(x);
-            }
-        } else {
-            let mut tmp = Vec::new();
-            for _i in 0..m.num {
-                let x = stacks[m.from].pop().unwrap();
-                tmp.push(x);
-            }
-            for _i in 0..m.num {
-                let x = tmp.pop().unwrap();
-                stacks[m.to].push(x);
-            }
-        }
-    }
+    Ok((parse_stacks(&stacks)?, parse_moves(&moves)?))
 }
 
 fn get_tops(stacks: &Stacks) -> String {
@@ -103,25 +291,76 @@ fn get_tops(stacks: &Stacks) -> String {
     tops
 }
 
-fn process1(lines: &mut Vec<String>) -> String {
-    let (mut stacks, moves) = parse_input(lines);
-    execute_moves(&mut stacks, moves, false);
-    get_tops(&stacks)
+fn process<C: Crane>(lines: &[String], crane: C) -> Result<String, InputError> {
+    let (mut stacks, moves) = parse_input(lines)?;
+    crane.run(&mut stacks, &moves)?;
+    Ok(get_tops(&stacks))
+}
+
+// the drawing before the first move and after each one
+fn show<C: Crane>(lines: &[String], crane: C) -> Result<(), InputError> {
+    let (mut stacks, moves) = parse_input(lines)?;
+    print!("{}", render(&stacks));
+    for (index, m) in moves.into_iter().enumerate() {
+        crane
+            .apply(&mut stacks, m)
+            .map_err(|fault| MoveError { index, fault })?;
+        println!("\nmove {} from {} to {}", m.num, m.from, m.to);
+        print!("{}", render(&stacks));
+    }
+    Ok(())
+}
+
+fn process1(lines: &[String]) -> Result<String, InputError> {
+    process(lines, CrateMover9000)
 }
 
-fn process2(lines: &mut Vec<String>) -> String {
-    let (mut stacks, moves) = parse_input(lines);
-    execute_moves(&mut stacks, moves, true);
-    get_tops(&stacks)
+fn process2(lines: &[String]) -> Result<String, InputError> {
+    process(lines, CrateMover9001)
 }
 
 fn main() {
     const INPUT: &str = "input.txt";
-    let mut lines = read_lines(INPUT);
-    let total = process1(&mut lines);
-    println!("1: {total}");
-    let total = process2(&mut lines);
-    println!("2: {total}");
+    let lines = read_lines(INPUT);
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(|x| x.as_str()).collect::<Vec<_>>();
+    match args[..] {
+        ["show", model] => {
+            let shown = match model {
+                "9000" => show(&lines, CrateMover9000),
+                "9001" => show(&lines, CrateMover9001),
+                _ => return println!("usage: day05 [show 9000|9001 | LIFT]"),
+            };
+            if let Err(e) = shown {
+                println!("{e:?}");
+            }
+            return;
+        }
+        [lift] => {
+            let Ok(lift) = lift.parse() else {
+                return println!("usage: day05 [show 9000|9001 | LIFT]");
+            };
+            let crane = Limited {
+                crane: CrateMover9001,
+                lift,
+            };
+            match process(&lines, crane) {
+                Ok(total) => println!("{lift} a lift: {total}"),
+                Err(e) => println!("{lift} a lift: {e:?}"),
+            }
+            return;
+        }
+        [] => (),
+        _ => return println!("usage: day05 [show 9000|9001 | LIFT]"),
+    }
+    match process1(&lines) {
+        Ok(total) => println!("1: {total}"),
+        Err(e) => println!("1: {e:?}"),
+    }
+    match process2(&lines) {
+        Ok(total) => println!("2: {total}"),
+        Err(e) => println!("2: {e:?}"),
+    }
 }
 
 #[cfg(test)]
@@ -137,34 +376,49 @@ mod tests {
         move 1 from 1 to 2";
 
     fn const_to_lines(c: &str) -> Vec<String> {
-        c.lines().map(|x| String::from(x)).collect()
+        c.lines().map(String::from).collect()
     }
 
     #[test]
     fn test_simple() {
-        let mut lines = const_to_lines(EXAMPLE);
-        let (stacks, moves) = split_input(&mut lines);
+        let lines = const_to_lines(EXAMPLE);
+        let (stacks, moves) = split_input(&lines);
         assert_eq!(stacks.len(), 4);
         assert_eq!(moves.len(), 4);
-        let stacks = parse_stacks(stacks);
+        let stacks = parse_stacks(&stacks).unwrap();
         assert_eq!(
             stacks,
             vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]
         );
-        let moves = parse_moves(moves);
+        assert_eq!(moves[0].0, 6);
+        let moves = parse_moves(&moves).unwrap();
         assert_eq!(
             moves[0],
             Move {
                 num: 1,
-                from: 1,
-                to: 0,
+                from: 2,
+                to: 1,
             }
         );
     }
 
-    fn test_example(t: fn(&mut Vec<String>) -> String, expected: &str) {
-        let mut lines = const_to_lines(EXAMPLE);
-        assert_eq!(t(&mut lines), expected.to_string());
+    fn test_example(
+        t: fn(&[String]) -> Result<String, InputError>, expected: &str,
+    ) {
+        let lines = const_to_lines(EXAMPLE);
+        assert_eq!(t(&lines), Ok(expected.to_string()));
+    }
+
+    #[test]
+    fn test_endings() {
+        let crlf = EXAMPLE.replace('\n', "\r\n") + "\r\n\r\n";
+        let spaced = EXAMPLE
+            .replace("\n\n", "\n\n\n")
+            .replace("move 3 from 1 to 3\n", "move 3 from 1 to 3\n\n");
+        for input in [crlf, spaced] {
+            let lines = const_to_lines(&input);
+            assert_eq!(process1(&lines), Ok("CMZ".to_string()));
+        }
     }
 
     #[test]
@@ -176,4 +430,156 @@ mod tests {
     fn example2() {
         test_example(process2, "MCD");
     }
+
+    fn stacks() -> Stacks {
+        vec![vec!['A', 'B', 'C', 'D', 'E'], vec![], vec!['X']]
+    }
+
+    #[test]
+    fn test_cranes() {
+        let m = Move {
+            num: 5,
+            from: 1,
+            to: 2,
+        };
+        let mut s = stacks();
+        CrateMover9000.apply(&mut s, m).unwrap();
+        assert_eq!(s[1], ['E', 'D', 'C', 'B', 'A']);
+        let mut s = stacks();
+        CrateMover9001.apply(&mut s, m).unwrap();
+        assert_eq!(s[1], ['A', 'B', 'C', 'D', 'E']);
+
+        let crane = Limited {
+            crane: CrateMover9001,
+            lift: 2,
+        };
+        let mut s = stacks();
+        crane.apply(&mut s, m).unwrap();
+        assert_eq!(s[1], ['D', 'E', 'B', 'C', 'A']);
+        let crane = Limited {
+            crane: CrateMover9001,
+            lift: 1,
+        };
+        let mut s = stacks();
+        crane.apply(&mut s, m).unwrap();
+        assert_eq!(s[1], ['E', 'D', 'C', 'B', 'A']);
+
+        let crane = Limited {
+            crane: CrateMover9001,
+            lift: 0,
+        };
+        let mut s = stacks();
+        assert_eq!(crane.apply(&mut s, m), Err(MoveFault::NoLift));
+        assert_eq!(s, stacks());
+    }
+
+    #[test]
+    fn test_errors() {
+        let moves = [
+            Move {
+                num: 2,
+                from: 1,
+                to: 3,
+            },
+            Move {
+                num: 4,
+                from: 1,
+                to: 2,
+            },
+        ];
+        let mut s = stacks();
+        assert_eq!(
+            CrateMover9000.run(&mut s, &moves),
+            Err(MoveError {
+                index: 1,
+                fault: MoveFault::Short { stack: 1, has: 3 }
+            })
+        );
+        assert_eq!(s, [vec!['A', 'B', 'C'], vec![], vec!['X', 'E', 'D']]);
+
+        let crane = Limited {
+            crane: CrateMover9001,
+            lift: 1,
+        };
+        let mut s = stacks();
+        let m = Move {
+            num: 6,
+            from: 1,
+            to: 2,
+        };
+        let fault = MoveFault::Short { stack: 1, has: 5 };
+        assert_eq!(crane.apply(&mut s, m), Err(fault));
+        assert_eq!(s, stacks());
+
+        for (from, to, label) in [(0, 1, 0), (1, 4, 4), (4, 1, 4)] {
+            let m = Move { num: 1, from, to };
+            let fault = MoveFault::NoStack(label);
+            assert_eq!(CrateMover9001.apply(&mut s, m), Err(fault));
+            assert_eq!(crane.apply(&mut s, m), Err(fault));
+        }
+
+        let mut lines = const_to_lines(EXAMPLE);
+        lines.push("move 1 from 0 to 1".to_string());
+        let fault = MoveFault::NoStack(0);
+        assert_eq!(
+            process1(&lines),
+            Err(InputError::Move(MoveError { index: 4, fault }))
+        );
+        let bad_moves = ["move 1 from 2", "move x from 1 to 2", "take 1 to 2"];
+        for bad in bad_moves {
+            let mut lines = const_to_lines(EXAMPLE);
+            lines.insert(7, bad.to_string());
+            let err = InputError::BadMove { line: 8 };
+            assert_eq!(process1(&lines), Err(err), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let lines = const_to_lines(EXAMPLE);
+        let (drawing, _) = split_input(&lines);
+        let stacks = parse_stacks(&drawing).unwrap();
+        let drawing = drawing.iter().map(|x| format!("{x}\n"));
+        assert_eq!(render(&stacks), drawing.collect::<String>());
+
+        // trimmed lines and labels past 9
+        let mut stacks = vec![vec![]; 12];
+        stacks[0] = vec!['A', 'B'];
+        stacks[9] = vec!['J'];
+        stacks[11] = vec!['L', 'M', 'N'];
+        let drawing = render(&stacks);
+        assert!(drawing.ends_with(" 9   10  11  12\n"));
+        let trimmed = drawing.lines().map(|x| x.trim_end().to_string());
+        let trimmed = trimmed.collect::<Vec<_>>();
+        assert_eq!(trimmed[0], format!("{}[N]", " ".repeat(44)));
+        assert_eq!(parse_stacks(&trimmed), Ok(stacks.clone()));
+
+        stacks.resize(100, vec!['Z']);
+        let drawing = const_to_lines(&render(&stacks));
+        assert!(drawing[3].ends_with(" 99   100"));
+        assert_eq!(parse_stacks(&drawing), Ok(stacks));
+        assert_eq!(render(&vec![]), "\n");
+    }
+
+    #[test]
+    fn test_drawing_errors() {
+        let check = |s: &str| parse_stacks(&const_to_lines(s));
+        let at = |row, col| Position { row, col };
+        assert_eq!(check(""), Err(DrawingError::NoLabels));
+        assert_eq!(check("[A]\n   "), Err(DrawingError::NoLabels));
+        assert_eq!(check("[A]\n 2"), Err(DrawingError::BadLabel { col: 1 }));
+        assert_eq!(
+            check("[A] [B\n 1   2"),
+            Err(DrawingError::Malformed(at(0, 4)))
+        );
+        assert_eq!(
+            check("[A] [B]\n 1"),
+            Err(DrawingError::Unlabeled(at(0, 4)))
+        );
+        assert_eq!(
+            check("    [B]\n[A]\n 1   2"),
+            Err(DrawingError::Floating(at(0, 4)))
+        );
+        assert_eq!(check("[A]\n1 2 3"), Ok(vec![vec!['A'], vec![], vec![]]));
+    }
 }
@@ -1,11 +1,113 @@
+use std::cmp::Reverse;
+use std::collections::VecDeque;
 use std::fmt::Display;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Lines};
+use std::io::{BufRead, BufReader};
 use std::path::Path;
 
 const INPUT: &str = "input.txt";
 
-fn read_lines<N>(name: N) -> Lines<BufReader<File>>
+// words and the digit each one spells
+type Vocabulary<'a> = [(&'a str, u32)];
+
+const DIGITS: &Vocabulary = &[
+    ("0", 0), ("1", 1), ("2", 2), ("3", 3), ("4", 4),
+    ("5", 5), ("6", 6), ("7", 7), ("8", 8), ("9", 9),
+];
+
+const ENGLISH: &Vocabulary = &[
+    ("zero", 0), ("one", 1), ("two", 2), ("three", 3), ("four", 4),
+    ("five", 5), ("six", 6), ("seven", 7), ("eight", 8), ("nine", 9),
+];
+
+// an Aho-Corasick automaton with every transition filled in, so scanning
+// takes one step per byte.  out holds the (length, digit) of each word
+// ending at a state, including those reached through its failure links
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Scanner {
+    next: Vec<[usize; 256]>,
+    out: Vec<Vec<(usize, u32)>>,
+}
+
+impl Scanner {
+    fn new(words: &Vocabulary) -> Scanner {
+        let mut s = Scanner {
+            next: vec![[0; 256]],
+            out: vec![Vec::new()],
+        };
+
+        // the trie, where 0 is both the root and "no child yet"
+        for (word, digit) in words {
+            let mut state = 0;
+            for b in word.bytes() {
+                state = match s.next[state][b as usize] {
+                    0 => {
+                        s.next.push([0; 256]);
+                        s.out.push(Vec::new());
+                        s.next[state][b as usize] = s.next.len() - 1;
+                        s.next.len() - 1
+                    }
+                    n => n,
+                };
+            }
+            s.out[state].push((word.len(), *digit));
+        }
+
+        // breadth first, so a state's failure link is finished before it
+        let mut fail = vec![0; s.next.len()];
+        let mut queue = s.next[0]
+            .iter()
+            .copied()
+            .filter(|n| *n != 0)
+            .collect::<VecDeque<_>>();
+        while let Some(state) = queue.pop_front() {
+            for b in 0..256 {
+                let child = s.next[state][b];
+                let via = s.next[fail[state]][b];
+                if child == 0 {
+                    s.next[state][b] = via;
+                    continue;
+                }
+                fail[child] = via;
+                let inherited = s.out[via].clone();
+                s.out[child].extend(inherited);
+                queue.push_back(child);
+            }
+        }
+        s
+    }
+
+    // every (start, end, digit) in the line, in order of where words end
+    fn matches<'a>(
+        &'a self, line: &'a str,
+    ) -> impl Iterator<Item = (usize, usize, u32)> + 'a {
+        line.bytes()
+            .enumerate()
+            .scan(0, |state, (i, b)| {
+                *state = self.next[*state][b as usize];
+                Some((i + 1, *state))
+            })
+            .flat_map(|(end, state)| {
+                self.out[state]
+                    .iter()
+                    .map(move |(len, d)| (end - len, end, *d))
+            })
+    }
+
+    // the digits of the words starting furthest left and furthest right,
+    // taking the longer word when two start together
+    fn first_last(&self, line: &str) -> Option<(u32, u32)> {
+        let first = self.matches(line).min_by_key(|m| (m.0, Reverse(m.1)))?;
+        let last = self.matches(line).max_by_key(|m| (m.0, m.1))?;
+        Some((first.2, last.2))
+    }
+
+    fn calibration(&self, line: &str) -> u32 {
+        self.first_last(line).map_or(0, |(a, b)| 10 * a + b)
+    }
+}
+
+fn read_lines<N>(name: N) -> Vec<String>
 where
     N: AsRef<Path> + Display,
 {
@@ -13,43 +115,90 @@ where
         Ok(f) => f,
         Err(e) => panic!("open input error {e}"),
     };
-    BufReader::new(file).lines()
+    BufReader::new(file).lines().map(|x| x.unwrap()).collect()
 }
 
-fn main() {
-    let map = vec![
-        ("0", 0), ("1", 1), ("2", 2), ("3", 3), ("4", 4),
-        ("5", 5), ("6", 6), ("7", 7), ("8", 8), ("9", 9),
-        ("zero", 0), ("one", 1), ("two", 2), ("three", 3), ("four", 4),
-        ("five", 5), ("six", 6), ("seven", 7), ("eight", 8), ("nine", 9),
-    ];
+fn process(lines: &[String], words: &[&Vocabulary]) -> u32 {
+    let scanner = Scanner::new(&words.concat());
+    lines.iter().map(|l| scanner.calibration(l)).sum()
+}
+
+fn process1(lines: &[String]) -> u32 {
+    process(lines, &[DIGITS])
+}
+
+fn process2(lines: &[String]) -> u32 {
+    process(lines, &[DIGITS, ENGLISH])
+}
 
+fn main() {
     let lines = read_lines(INPUT);
-    let mut total = 0;
+    let total = process1(&lines);
+    println!("1: {total}");
+    let total = process2(&lines);
+    println!("2: {total}");
+}
 
-    for line in lines {
-        let mut first = None;
-        let mut last = None;
-        let line = line.unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        for i in 0..line.len() {
-            for (s, num) in &map {
-                if !line[i..].starts_with(s) {
-                    continue;
-                }
-                (first, last) = match (first, last) {
-                    (None, _) => (Some(num), Some(num)),
-                    (Some(a), _) => (Some(a), Some(num)),
-                };
-                break;
-            }
-        }
+    const EXAMPLE1: &str = "1abc2\n\
+                            pqr3stu8vwx\n\
+                            a1b2c3d4e5f\n\
+                            treb7uchet";
 
-        total += match (first, last) {
-            (Some(a), Some(b)) => 10 * a + b,
-            _ => 0,
-        };
+    const EXAMPLE2: &str = "two1nine\n\
+                            eightwothree\n\
+                            abcone2threexyz\n\
+                            xtwone3four\n\
+                            4nineeightseven2\n\
+                            zoneight234\n\
+                            7pqrstsixteen";
+
+    fn const_to_lines(c: &str) -> Vec<String> {
+        c.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_overlap() {
+        let s = Scanner::new(&[DIGITS, ENGLISH].concat());
+        assert_eq!(s.first_last("eightwo"), Some((8, 2)));
+        assert_eq!(s.first_last("twone"), Some((2, 1)));
+        assert_eq!(s.first_last("oneight"), Some((1, 8)));
+        assert_eq!(s.first_last("sevenine"), Some((7, 9)));
+        assert_eq!(s.first_last("xyz"), None);
+        assert_eq!(s.calibration("xyz"), 0);
+        let m = s.matches("eightwo").collect::<Vec<_>>();
+        assert_eq!(m, [(0, 5, 8), (4, 7, 2)]);
+
+        let s = Scanner::new(DIGITS);
+        assert_eq!(s.first_last("eightwo7"), Some((7, 7)));
     }
 
-    println!("{total}");
+    #[test]
+    fn test_vocabulary() {
+        const GERMAN: &Vocabulary = &[
+            ("eins", 1), ("zwei", 2), ("drei", 3), ("vier", 4), ("fünf", 5),
+            ("sechs", 6), ("sieben", 7), ("acht", 8), ("neun", 9), ("ein", 1),
+        ];
+        let s = Scanner::new(&[DIGITS, GERMAN].concat());
+        assert_eq!(s.first_last("xfünfzweiy"), Some((5, 2)));
+        assert_eq!(s.first_last("achteins"), Some((8, 1)));
+        assert_eq!(s.first_last("neunein"), Some((9, 1)));
+        let m = s.matches("einsieben").collect::<Vec<_>>();
+        assert_eq!(m, [(0, 3, 1), (0, 4, 1), (3, 9, 7)]);
+        assert_eq!(s.first_last("4sechs"), Some((4, 6)));
+        assert_eq!(s.first_last("three"), None);
+    }
+
+    #[test]
+    fn example1() {
+        assert_eq!(process1(&const_to_lines(EXAMPLE1)), 142);
+    }
+
+    #[test]
+    fn example2() {
+        assert_eq!(process2(&const_to_lines(EXAMPLE2)), 281);
+    }
 }
@@ -1,16 +1,18 @@
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use std::cmp::max;
-use std::collections::HashMap;
-use std::fmt::Display;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fmt::{self, Display, Formatter};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// how many cubes of each color; a color that isn't there counts as 0
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 struct Pick {
-    red: u64,
-    green: u64,
-    blue: u64,
+    counts: BTreeMap<String, u64>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -20,27 +22,79 @@ struct Game {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct ParsePickError;
+enum ParsePickError {
+    Count,
+    Color,
+    Repeated,
+}
 
 impl FromStr for Pick {
     type Err = ParsePickError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let colors = s
-            .split(", ")
-            .map(|c| c.split(" ").collect::<Vec<_>>())
-            .map(|c| (c[1], c[0].parse().unwrap()))
-            .collect::<HashMap<_, _>>();
-        Ok(Pick {
-            red: *colors.get("red").unwrap_or(&0),
-            green: *colors.get("green").unwrap_or(&0),
-            blue: *colors.get("blue").unwrap_or(&0),
-        })
+        let mut pick = Pick::default();
+        for c in s.split(',') {
+            let (n, color) =
+                c.trim().split_once(' ').ok_or(ParsePickError::Color)?;
+            let n = n.parse().map_err(|_| ParsePickError::Count)?;
+            let color = color.trim();
+            if color.is_empty() || color.contains(char::is_whitespace) {
+                return Err(ParsePickError::Color);
+            }
+            if pick.counts.insert(color.to_string(), n).is_some() {
+                return Err(ParsePickError::Repeated);
+            }
+        }
+        Ok(pick)
+    }
+}
+
+impl Display for Pick {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let counts = self.counts.iter().map(|(c, n)| format!("{n} {c}"));
+        write!(f, "{}", counts.collect::<Vec<_>>().join(", "))
+    }
+}
+
+impl Pick {
+    fn count(&self, color: &str) -> u64 {
+        self.counts.get(color).copied().unwrap_or(0)
+    }
+
+    fn fits(&self, bag: &Pick) -> bool {
+        self.counts.iter().all(|(c, n)| *n <= bag.count(c))
+    }
+
+    // the smallest pick holding both
+    fn union(&mut self, other: &Pick) {
+        for (c, n) in &other.counts {
+            let m = self.counts.entry(c.clone()).or_default();
+            *m = max(*m, *n);
+        }
+    }
+
+    fn power<'a>(&self, colors: impl IntoIterator<Item = &'a str>) -> u64 {
+        colors.into_iter().map(|c| self.count(c)).product()
+    }
+
+    fn over(&self, colors: &[&str]) -> Vec<u64> {
+        colors.iter().map(|c| self.count(c)).collect()
+    }
+
+    fn from_counts(colors: &[&str], counts: &[u64]) -> Pick {
+        let counts = colors.iter().zip(counts).filter(|(_, n)| **n > 0);
+        Pick {
+            counts: counts.map(|(c, n)| (c.to_string(), *n)).collect(),
+        }
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct ParseGameError;
+enum ParseGameError {
+    Header,
+    Id,
+    Pick(ParsePickError),
+}
 
 impl FromStr for Game {
     type Err = ParseGameError;
@@ -49,31 +103,164 @@ impl FromStr for Game {
         let (id, picks) = s
             .strip_prefix("Game ")
             .and_then(|s| s.split_once(": "))
-            .ok_or(ParseGameError)?;
-        let picks = picks.split("; ").map(|p| p.parse().unwrap()).collect();
+            .ok_or(ParseGameError::Header)?;
+        let picks = picks
+            .split("; ")
+            .map(|p| p.parse())
+            .collect::<Result<_, _>>()
+            .map_err(ParseGameError::Pick)?;
         Ok(Game {
-            id: id.parse().unwrap(),
-            picks: picks,
+            id: id.parse().map_err(|_| ParseGameError::Id)?,
+            picks,
         })
     }
 }
 
 impl Game {
-    fn compat(&self, have: &Pick) -> bool {
+    fn possible(&self, bag: &Pick) -> bool {
+        self.picks.iter().all(|p| p.fits(bag))
+    }
+
+    fn minimal(&self) -> Pick {
+        let mut bag = Pick::default();
         for p in &self.picks {
-            if p.red > have.red || p.green > have.green || p.blue > have.blue {
-                return false;
+            bag.union(p);
+        }
+        bag
+    }
+
+    fn draws(&self, colors: &[&str]) -> Vec<Vec<u64>> {
+        self.picks.iter().map(|p| p.over(colors)).collect()
+    }
+
+    // the bag making the picks most likely, the first in bags() order on
+    // ties; None if no bag of at most `total` cubes could give them
+    fn most_likely(&self, colors: &[&str], total: u64) -> Option<Pick> {
+        let draws = self.draws(colors);
+        let mut best: Option<(f64, Vec<u64>)> = None;
+        for bag in bags(&self.minimal().over(colors), total) {
+            let l = ln_likelihood(&draws, &bag);
+            if best.as_ref().is_none_or(|b| l > b.0) {
+                best = Some((l, bag));
             }
         }
-        true
+        best.map(|b| Pick::from_counts(colors, &b.1))
+    }
+
+    // the probability of each possible bag given the picks
+    fn posterior(
+        &self, colors: &[&str], total: u64, prior: Prior,
+    ) -> Vec<(Pick, f64)> {
+        let draws = self.draws(colors);
+        let bags = bags(&self.minimal().over(colors), total);
+        let ln = bags
+            .iter()
+            .map(|b| prior.ln_weight(b) + ln_likelihood(&draws, b))
+            .collect::<Vec<_>>();
+        // scale by the largest term before exp so nothing underflows
+        let top = ln.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let weights = ln.iter().map(|l| (l - top).exp()).collect::<Vec<_>>();
+        let sum = weights.iter().sum::<f64>();
+        bags.iter()
+            .zip(weights)
+            .map(|(b, w)| (Pick::from_counts(colors, b), w / sum))
+            .collect()
+    }
+}
+
+// every color seen in any game
+fn colors(games: &[Game]) -> BTreeSet<&str> {
+    let picks = games.iter().flat_map(|g| &g.picks);
+    picks
+        .flat_map(|p| p.counts.keys().map(|c| c.as_str()))
+        .collect()
+}
+
+fn smallest_bag(games: &[Game]) -> Pick {
+    let mut bag = Pick::default();
+    for g in games {
+        bag.union(&g.minimal());
+    }
+    bag
+}
+
+// prior weight of a bag before seeing any picks: every bag alike, or each
+// color's count independently Poisson with the given mean
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Prior {
+    Uniform,
+    Poisson(f64),
+}
+
+// a Poisson mean that isn't a positive number
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PriorError(f64);
+
+impl Prior {
+    fn poisson(mean: f64) -> Result<Prior, PriorError> {
+        if !(mean > 0.0 && mean.is_finite()) {
+            return Err(PriorError(mean));
+        }
+        Ok(Prior::Poisson(mean))
+    }
+
+    fn ln_weight(&self, bag: &[u64]) -> f64 {
+        match self {
+            Prior::Uniform => 0.0,
+            Prior::Poisson(mean) => bag
+                .iter()
+                .map(|n| *n as f64 * mean.ln() - ln_factorial(*n))
+                .sum(),
+        }
+    }
+}
+
+fn ln_factorial(n: u64) -> f64 {
+    (2..=n).map(|i| (i as f64).ln()).sum()
+}
+
+fn ln_choose(n: u64, k: u64) -> f64 {
+    if k > n {
+        return f64::NEG_INFINITY;
+    }
+    (0..k).map(|i| ((n - i) as f64 / (i + 1) as f64).ln()).sum()
+}
+
+// every bag holding at least `min` and at most `total` cubes, with counts
+// in the same color order as `min`, in lexicographic order
+fn bags(min: &[u64], total: u64) -> Vec<Vec<u64>> {
+    let Some((first, rest)) = min.split_first() else {
+        return vec![Vec::new()];
+    };
+    let mut out = Vec::new();
+    for n in *first..=total {
+        for mut bag in bags(rest, total - n) {
+            bag.insert(0, n);
+            out.push(bag);
+        }
     }
+    out
+}
 
-    fn power(&self) -> u64 {
-        let r = self.picks.iter().fold(0, |r, p| max(r, p.red));
-        let g = self.picks.iter().fold(0, |g, p| max(g, p.green));
-        let b = self.picks.iter().fold(0, |b, p| max(b, p.blue));
-        r * g * b
+// each pick draws its cubes from the bag without replacement, and they go
+// back before the next pick
+fn ln_likelihood(draws: &[Vec<u64>], bag: &[u64]) -> f64 {
+    let total = bag.iter().sum();
+    let mut l = 0.0;
+    for d in draws {
+        for (n, k) in bag.iter().zip(d) {
+            l += ln_choose(*n, *k);
+        }
+        l -= ln_choose(total, d.iter().sum());
     }
+    l
+}
+
+fn mean(posterior: &[(Pick, f64)], color: &str) -> f64 {
+    posterior
+        .iter()
+        .map(|(b, p)| b.count(color) as f64 * p)
+        .sum()
 }
 
 fn read_lines<N>(name: N) -> Vec<String>
@@ -87,32 +274,108 @@ where
     BufReader::new(file).lines().map(|x| x.unwrap()).collect()
 }
 
-fn process1(lines: &Vec<String>) -> u64 {
-    let have = Pick {
-        red: 12,
-        green: 13,
-        blue: 14,
-    };
-    lines
-        .iter()
-        .map(|x| x.parse::<Game>().unwrap())
-        .filter_map(|x| if x.compat(&have) { Some(x.id) } else { None })
-        .sum()
+fn parse(lines: &[String]) -> Result<Vec<Game>, ParseGameError> {
+    #[cfg(feature = "parallel")]
+    let lines = lines.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let lines = lines.iter();
+    lines.map(|x| x.parse::<Game>()).collect()
 }
 
-fn process2(lines: &Vec<String>) -> u64 {
-    lines
+fn possible(games: &[Game], bag: &Pick) -> Vec<u64> {
+    let games = games.iter().filter(|g| g.possible(bag));
+    games.map(|g| g.id).collect()
+}
+
+const BAG: &str = "12 red, 13 green, 14 blue";
+
+fn process1(lines: &[String]) -> Result<u64, ParseGameError> {
+    let bag = BAG.parse().unwrap();
+    Ok(possible(&parse(lines)?, &bag).iter().sum())
+}
+
+// the power counts every color any game shows, so a game missing one of
+// them has power 0
+fn process2(lines: &[String]) -> Result<u64, ParseGameError> {
+    let games = parse(lines)?;
+    let colors = colors(&games);
+    Ok(games
         .iter()
-        .fold(0, |t, x| t + x.parse::<Game>().unwrap().power())
+        .map(|g| g.minimal().power(colors.iter().copied()))
+        .sum())
+}
+
+fn estimate(games: &[Game], total: u64, prior: Prior) {
+    let colors = colors(games).into_iter().collect::<Vec<_>>();
+    for g in games {
+        let Some(ml) = g.most_likely(&colors, total) else {
+            println!("{}: needs more than {total} cubes", g.id);
+            continue;
+        };
+        let post = g.posterior(&colors, total, prior);
+        let means = colors.iter().map(|c| format!("{:.2} {c}", mean(&post, c)));
+        let means = means.collect::<Vec<_>>().join(", ");
+        println!("{}: most likely {ml}; mean {means}", g.id);
+    }
 }
 
 fn main() {
     const INPUT: &str = "input.txt";
     let lines = read_lines(INPUT);
-    let total = process1(&lines);
-    println!("1: {total}");
-    let total = process2(&lines);
-    println!("2: {total}");
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(|x| x.as_str()).collect::<Vec<_>>();
+    if !args.is_empty() {
+        let games = match parse(&lines) {
+            Ok(games) => games,
+            Err(e) => return println!("{e:?}"),
+        };
+        match args[..] {
+            ["possible", bag] => match bag.parse() {
+                Ok(bag) => println!("{:?}", possible(&games, &bag)),
+                Err(e) => println!("{e:?}"),
+            },
+            ["minimal"] => {
+                let colors = colors(&games);
+                for g in &games {
+                    let bag = g.minimal();
+                    let power = bag.power(colors.iter().copied());
+                    println!("{}: {bag} (power {power})", g.id);
+                }
+            }
+            ["smallest"] => println!("{}", smallest_bag(&games)),
+            ["estimate", total, ref prior @ ..] => {
+                let prior = match prior {
+                    [] | ["uniform"] => Ok(Prior::Uniform),
+                    ["poisson", mean] => match mean.parse() {
+                        Ok(mean) => Prior::poisson(mean),
+                        Err(_) => return println!("bad mean {mean}"),
+                    },
+                    _ => return println!("prior: uniform | poisson MEAN"),
+                };
+                let Ok(total) = total.parse() else {
+                    return println!("bad total {total}");
+                };
+                match prior {
+                    Ok(prior) => estimate(&games, total, prior),
+                    Err(e) => println!("{e:?}"),
+                }
+            }
+            _ => println!(
+                "usage: day02 [possible BAG | minimal | smallest | \
+                 estimate TOTAL [uniform | poisson MEAN]]"
+            ),
+        }
+        return;
+    }
+
+    match process1(&lines) {
+        Ok(total) => println!("1: {total}"),
+        Err(e) => println!("1: {e:?}"),
+    }
+    match process2(&lines) {
+        Ok(total) => println!("2: {total}"),
+        Err(e) => println!("2: {e:?}"),
+    }
 }
 
 #[cfg(test)]
@@ -126,10 +389,13 @@ mod tests {
     Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red\n\
     Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
 
-    fn test(t: fn(&Vec<String>) -> u64, expected: u64) {
-        let lines = EXAMPLE.lines().map(|x| String::from(x)).collect();
-        let actual = t(&lines);
-        assert_eq!(actual, expected);
+    fn lines(s: &str) -> Vec<String> {
+        s.lines().map(String::from).collect()
+    }
+
+    fn test(t: fn(&[String]) -> Result<u64, ParseGameError>, expected: u64) {
+        let actual = t(&lines(EXAMPLE));
+        assert_eq!(actual, Ok(expected));
     }
 
     #[test]
@@ -141,4 +407,119 @@ mod tests {
     fn example2() {
         test(process2, 2286);
     }
+
+    fn pick(s: &str) -> Pick {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_pick() {
+        let p = pick("3 blue, 4 red, 1 mauve");
+        assert_eq!(p.count("mauve"), 1);
+        assert_eq!(p.count("green"), 0);
+        assert_eq!(p.to_string(), "3 blue, 1 mauve, 4 red");
+        assert!(p.fits(&pick("5 red, 3 blue, 2 mauve")));
+        assert!(!p.fits(&pick("5 red, 3 blue")));
+
+        assert_eq!("x red".parse::<Pick>(), Err(ParsePickError::Count));
+        assert_eq!("-1 red".parse::<Pick>(), Err(ParsePickError::Count));
+        assert_eq!("3".parse::<Pick>(), Err(ParsePickError::Color));
+        assert_eq!("3 dark red".parse::<Pick>(), Err(ParsePickError::Color));
+        assert_eq!(
+            "1 red, 2 red".parse::<Pick>(),
+            Err(ParsePickError::Repeated)
+        );
+        assert_eq!("Game x: 1 red".parse::<Game>(), Err(ParseGameError::Id));
+        assert_eq!("1 red".parse::<Game>(), Err(ParseGameError::Header));
+        assert_eq!(
+            "Game 1: 1 red; red".parse::<Game>(),
+            Err(ParseGameError::Pick(ParsePickError::Color))
+        );
+    }
+
+    #[test]
+    fn test_queries() {
+        let games = parse(&lines(EXAMPLE)).unwrap();
+        assert_eq!(possible(&games, &pick(BAG)), [1, 2, 5]);
+        assert_eq!(
+            possible(&games, &pick("20 red, 13 green, 15 blue")).len(),
+            5
+        );
+        assert_eq!(games[0].minimal(), pick("4 red, 2 green, 6 blue"));
+        let smallest = smallest_bag(&games);
+        assert_eq!(smallest, pick("20 red, 13 green, 15 blue"));
+        assert!(games.iter().all(|g| g.possible(&smallest)));
+
+        let games = parse(&lines(
+            "Game 1: 2 red, 1 teal\n\
+             Game 2: 3 red; 4 teal, 1 gold",
+        ))
+        .unwrap();
+        assert_eq!(colors(&games).len(), 3);
+        assert_eq!(smallest_bag(&games), pick("3 red, 4 teal, 1 gold"));
+        assert_eq!(possible(&games, &pick("3 red, 4 teal")), [1]);
+        let total = process2(&lines("Game 1: 2 red, 1 teal\nGame 2: 3 red"));
+        assert_eq!(total, Ok(2));
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-12
+    }
+
+    #[test]
+    fn test_likelihood() {
+        assert!(close(ln_choose(5, 2), 10f64.ln()));
+        assert_eq!(ln_choose(2, 3), f64::NEG_INFINITY);
+        assert!(close(ln_factorial(5), 120f64.ln()));
+
+        let g = "Game 1: 1 red, 1 blue".parse::<Game>().unwrap();
+        let colors = ["blue", "red"];
+        let draws = g.draws(&colors);
+        assert_eq!(draws, [[1, 1]]);
+        assert!(close(ln_likelihood(&draws, &[2, 2]).exp(), 4.0 / 6.0));
+        assert_eq!(ln_likelihood(&draws, &[0, 2]), f64::NEG_INFINITY);
+        assert_eq!(bags(&[1, 1], 4).len(), 6);
+        assert_eq!(bags(&[0, 2], 3), [[0, 2], [0, 3], [1, 2]]);
+        assert_eq!(g.most_likely(&colors, 4), Some(pick("1 red, 1 blue")));
+        assert_eq!(g.most_likely(&colors, 1), None);
+
+        // likelihoods 1, 2/3, 1/2, 2/3, 2/3, 1/2 over bags() order
+        let post = g.posterior(&colors, 4, Prior::Uniform);
+        let want = [1.0, 2.0 / 3.0, 0.5, 2.0 / 3.0, 2.0 / 3.0, 0.5];
+        for ((_, p), w) in post.iter().zip(want) {
+            assert!(close(*p, w / 4.0));
+        }
+        assert!(close(mean(&post, "red"), 19.0 / 12.0));
+        assert!(close(mean(&post, "blue"), 19.0 / 12.0));
+    }
+
+    #[test]
+    fn test_prior() {
+        let g = "Game 1: 2 red; 2 red".parse::<Game>().unwrap();
+        let colors = ["blue", "red"];
+        // a flat prior can't tell bags of only red apart
+        let post = g.posterior(&colors, 6, Prior::Uniform);
+        let p = |post: &[(Pick, f64)], bag| {
+            post.iter().find(|x| x.0 == pick(bag)).unwrap().1
+        };
+        assert!(close(p(&post, "2 red"), p(&post, "6 red")));
+        assert!(close(p(&post, "2 red"), 0.15389876880984954));
+        assert!(close(post.iter().map(|x| x.1).sum::<f64>(), 1.0));
+
+        // a prior expecting many cubes of each color moves toward blue, one
+        // expecting few settles on the minimal bag
+        let post = g.posterior(&colors, 6, Prior::poisson(10.0).unwrap());
+        let best = post.iter().max_by(|a, b| a.1.total_cmp(&b.1)).unwrap();
+        assert_eq!(best.0, pick("1 blue, 5 red"));
+        assert!(mean(&post, "blue") > 1.0);
+        let post = g.posterior(&colors, 6, Prior::poisson(0.1).unwrap());
+        let best = post.iter().max_by(|a, b| a.1.total_cmp(&b.1)).unwrap();
+        assert_eq!(best.0, pick("2 red"));
+        assert!(mean(&post, "blue") < 0.02);
+
+        assert_eq!(Prior::poisson(0.0), Err(PriorError(0.0)));
+        assert_eq!(Prior::poisson(-1.5), Err(PriorError(-1.5)));
+        assert!(Prior::poisson(f64::NAN).is_err());
+        assert!(Prior::poisson(f64::INFINITY).is_err());
+    }
 }
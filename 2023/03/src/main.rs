@@ -1,10 +1,34 @@
-use std::cmp::min;
-use std::collections::HashSet;
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::ops::Range;
 use std::path::Path;
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Number {
+    value: u64,
+    row: usize,
+    cols: Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Symbol {
+    c: char,
+    row: usize,
+    col: usize,
+}
+
+// numbers and symbols in reading order; the adjacency lists hold indices
+// into the other vector, also in reading order
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Schematic {
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+    sym_nums: Vec<Vec<usize>>,
+    num_syms: Vec<Vec<usize>>,
+}
+
 fn read_lines<N>(name: N) -> Vec<String>
 where
     N: AsRef<Path> + Display,
@@ -16,105 +40,108 @@ where
     BufReader::new(file).lines().map(|x| x.unwrap()).collect()
 }
 
-fn split_grid(lines: &Vec<String>) -> Vec<Vec<char>> {
-    lines.iter().map(|x| x.chars().collect()).collect()
-}
-
-fn find_symbols(
-    grid: &Vec<Vec<char>>, only: Option<char>,
-) -> HashSet<(usize, usize)> {
-    let (len_x, len_y) = (grid[0].len(), grid.len());
-    let mut syms = HashSet::new();
-    for y in 0..len_y {
-        for x in 0..len_x {
-            if !grid[y][x].is_ascii_digit()
-                && grid[y][x] != '.'
-                && (only == None || only.unwrap() == grid[y][x])
-            {
-                syms.insert((x, y));
+fn tokenize(
+    row: usize, line: &str, numbers: &mut Vec<Number>,
+    symbols: &mut Vec<Symbol>,
+) {
+    let mut cur: Option<Number> = None;
+    for (col, c) in line.chars().enumerate() {
+        match (c.to_digit(10), &mut cur) {
+            (Some(d), Some(n)) => {
+                n.value = 10 * n.value + d as u64;
+                n.cols.end = col + 1;
             }
-        }
-    }
-    syms
-}
-
-fn find_coords(
-    grid: &Vec<Vec<char>>, syms: &HashSet<(usize, usize)>,
-) -> HashSet<(usize, usize)> {
-    let (len_x, len_y) = (grid[0].len(), grid.len());
-    let mut check = HashSet::new();
-    for (x, y) in syms {
-        for i in (x.saturating_sub(1))..=min(x + 1, len_x - 1) {
-            for j in (y.saturating_sub(1))..=min(y + 1, len_y - 1) {
-                check.insert((i, j));
+            (Some(d), None) => {
+                cur = Some(Number {
+                    value: d.into(),
+                    row,
+                    cols: col..col + 1,
+                });
+            }
+            (None, _) => {
+                numbers.extend(cur.take());
+                if c != '.' {
+                    symbols.push(Symbol { c, row, col });
+                }
             }
         }
-        check.remove(&(*x, *y));
     }
-    check
+    numbers.extend(cur);
 }
 
-fn get_part(
-    grid: &Vec<Vec<char>>, check: &mut HashSet<(usize, usize)>, x: usize,
-    y: usize,
-) -> Option<u64> {
-    if !check.contains(&(x, y)) || !grid[y][x].is_ascii_digit() {
-        None
-    } else {
-        let (mut left, mut right) = (x, x);
-        for x in (0..=left).rev() {
-            if !grid[y][x].is_ascii_digit() {
-                break;
-            }
-            left = x;
+impl Schematic {
+    fn new(lines: &[String]) -> Schematic {
+        let (mut numbers, mut symbols) = (Vec::new(), Vec::new());
+        for (row, line) in lines.iter().enumerate() {
+            tokenize(row, line, &mut numbers, &mut symbols);
         }
-        for x in right..grid[0].len() {
-            if !grid[y][x].is_ascii_digit() {
-                break;
+
+        let at = symbols
+            .iter()
+            .enumerate()
+            .map(|(i, s)| ((s.row, s.col), i))
+            .collect::<HashMap<_, _>>();
+        let mut sym_nums = vec![Vec::new(); symbols.len()];
+        let mut num_syms = vec![Vec::new(); numbers.len()];
+        for (i, n) in numbers.iter().enumerate() {
+            for row in n.row.saturating_sub(1)..=n.row + 1 {
+                for col in n.cols.start.saturating_sub(1)..=n.cols.end {
+                    if let Some(&s) = at.get(&(row, col)) {
+                        sym_nums[s].push(i);
+                        num_syms[i].push(s);
+                    }
+                }
             }
-            right = x;
         }
-        let mut part = 0;
-        for x in left..=right {
-            part = 10 * part + grid[y][x].to_digit(10).unwrap();
-            check.remove(&(x, y));
+
+        Schematic {
+            numbers,
+            symbols,
+            sym_nums,
+            num_syms,
         }
-        Some(part.into())
     }
-}
 
-fn find_parts(
-    grid: &Vec<Vec<char>>, check: &mut HashSet<(usize, usize)>,
-) -> Vec<u64> {
-    let mut parts = Vec::new();
-    for (x, y) in check.clone() {
-        if let Some(p) = get_part(grid, check, x, y) {
-            parts.push(p);
-        }
+    fn parts(&self) -> impl Iterator<Item = &Number> + '_ {
+        self.numbers
+            .iter()
+            .zip(&self.num_syms)
+            .filter(|(_, s)| !s.is_empty())
+            .map(|(n, _)| n)
+    }
+
+    fn loose(&self) -> impl Iterator<Item = &Number> + '_ {
+        self.numbers
+            .iter()
+            .zip(&self.num_syms)
+            .filter(|(_, s)| s.is_empty())
+            .map(|(n, _)| n)
+    }
+
+    fn neighbors(&self, sym: usize) -> impl Iterator<Item = &Number> + '_ {
+        self.sym_nums[sym].iter().map(|&n| &self.numbers[n])
+    }
+
+    // indices of symbols with exactly `count` adjacent numbers
+    fn symbols_with(
+        &self, count: usize, only: Option<char>,
+    ) -> impl Iterator<Item = usize> + '_ {
+        self.symbols.iter().enumerate().filter_map(move |(i, s)| {
+            let want = only.is_none_or(|c| c == s.c);
+            (want && self.sym_nums[i].len() == count).then_some(i)
+        })
     }
-    parts
 }
 
-fn process1(lines: &Vec<String>) -> u64 {
-    let grid = split_grid(lines);
-    let syms = find_symbols(&grid, None);
-    let mut check = find_coords(&grid, &syms);
-    let parts = find_parts(&grid, &mut check);
-    parts.iter().sum()
+fn process1(lines: &[String]) -> u64 {
+    Schematic::new(lines).parts().map(|n| n.value).sum()
 }
 
-fn process2(lines: &Vec<String>) -> u64 {
-    let grid = split_grid(lines);
-    let syms = find_symbols(&grid, Some('*'));
-    let mut gears = Vec::new();
-    for s in syms {
-        let mut check = find_coords(&grid, &HashSet::from([s]));
-        let parts = find_parts(&grid, &mut check);
-        if parts.len() == 2 {
-            gears.push(parts[0] * parts[1]);
-        }
-    }
-    gears.iter().sum()
+fn process2(lines: &[String]) -> u64 {
+    let s = Schematic::new(lines);
+    s.symbols_with(2, Some('*'))
+        .map(|g| s.neighbors(g).map(|n| n.value).product::<u64>())
+        .sum()
 }
 
 fn main() {
@@ -124,6 +151,10 @@ fn main() {
     println!("1: {total}");
     let total = process2(&lines);
     println!("2: {total}");
+
+    let s = Schematic::new(&lines);
+    let loose = s.loose().map(|n| n.value.to_string()).collect::<Vec<_>>();
+    println!("loose: {}", loose.join(" "));
 }
 
 #[cfg(test)]
@@ -146,23 +177,57 @@ mod tests {
     .664.598..";
 
     fn const_to_lines(c: &str) -> Vec<String> {
-        c.lines().map(|x| String::from(x)).collect()
+        c.lines().map(String::from).collect()
+    }
+
+    fn values<'a>(n: impl Iterator<Item = &'a Number>) -> Vec<u64> {
+        n.map(|n| n.value).collect()
     }
 
     #[test]
     fn test_simple() {
         let lines = const_to_lines(SIMPLE);
-        let grid = split_grid(&lines);
-        let syms = find_symbols(&grid, None);
-        assert_eq!(syms, HashSet::from([(0, 0)]));
-        let mut check = find_coords(&grid, &syms);
-        assert_eq!(check, HashSet::from([(0, 1), (1, 0), (1, 1)]));
-        let mut parts = find_parts(&grid, &mut check);
-        parts.sort();
-        assert_eq!(parts, [2, 100]);
+        let s = Schematic::new(&lines);
+        assert_eq!(
+            s.symbols,
+            vec![Symbol {
+                c: '*',
+                row: 0,
+                col: 0
+            }]
+        );
+        assert_eq!(
+            s.numbers[1],
+            Number {
+                value: 100,
+                row: 1,
+                cols: 0..3
+            }
+        );
+        assert_eq!(values(s.parts()), [2, 100]);
+        assert_eq!(values(s.neighbors(0)), [2, 100]);
+    }
+
+    #[test]
+    fn test_queries() {
+        let lines = const_to_lines(EXAMPLE);
+        let s = Schematic::new(&lines);
+        assert_eq!(values(s.loose()), [114, 58]);
+        assert_eq!(s.symbols_with(2, None).collect::<Vec<_>>(), [0, 5]);
+        let lonely = s.symbols_with(1, None).map(|i| s.symbols[i].c);
+        assert_eq!(lonely.collect::<String>(), "#*+$");
+        assert_eq!(values(s.neighbors(5)), [755, 598]);
+    }
+
+    #[test]
+    fn test_row_end() {
+        let lines = const_to_lines("..12\n...#\n7...");
+        let s = Schematic::new(&lines);
+        assert_eq!(values(s.parts()), [12]);
+        assert_eq!(values(s.loose()), [7]);
     }
 
-    fn test_example(t: fn(&Vec<String>) -> u64, expected: u64) {
+    fn test_example(t: fn(&[String]) -> u64, expected: u64) {
         let lines = const_to_lines(EXAMPLE);
         assert_eq!(t(&lines), expected);
     }
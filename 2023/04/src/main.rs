@@ -1,9 +1,45 @@
-use std::collections::HashSet;
+use std::env;
 use std::fmt::Display;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 
+// numbers on a card are below 128, one bit each
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Card {
+    winning: u128,
+    have: u128,
+}
+
+// lines and cards count from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CardError {
+    Format { line: usize },
+    Number { line: usize },
+    Overrun { card: usize },
+    Overflow { card: usize },
+}
+
+// what to do with wins that run past the last card
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Overrun {
+    Clamp,
+    Fail,
+}
+
+impl Card {
+    fn matches(&self) -> usize {
+        (self.winning & self.have).count_ones() as usize
+    }
+
+    fn points(&self) -> u128 {
+        match self.matches() {
+            0 => 0,
+            n => 1 << (n - 1),
+        }
+    }
+}
+
 fn read_lines<N>(name: N) -> Vec<String>
 where
     N: AsRef<Path> + Display,
@@ -15,66 +51,101 @@ where
     BufReader::new(file).lines().map(|x| x.unwrap()).collect()
 }
 
-fn parse_cards(cards: &Vec<String>) -> Vec<(HashSet<u64>, HashSet<u64>)> {
+fn parse_mask(numbers: &str, line: usize) -> Result<u128, CardError> {
+    let mut mask = 0;
+    for n in numbers.split_whitespace() {
+        match n.parse::<u32>() {
+            Ok(n) if n < 128 => mask |= 1 << n,
+            _ => return Err(CardError::Number { line }),
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_cards(cards: &[String]) -> Result<Vec<Card>, CardError> {
     let mut out = Vec::new();
-    for card in cards {
-        let parts = card.split(": ").collect::<Vec<_>>();
-        let parts = parts[1]
-            .split(" | ")
-            .map(|x| {
-                x.split_whitespace()
-                    .map(|y| y.parse::<u64>().unwrap())
-                    .collect::<Vec<_>>()
-            })
-            .collect::<Vec<_>>();
-        out.push((
-            HashSet::from_iter(parts[0].clone()),
-            HashSet::from_iter(parts[1].clone()),
-        ));
-    }
-    out
+    for (i, card) in cards.iter().enumerate() {
+        let line = i + 1;
+        let (winning, have) = card
+            .split_once(": ")
+            .and_then(|(_, x)| x.split_once(" | "))
+            .ok_or(CardError::Format { line })?;
+        out.push(Card {
+            winning: parse_mask(winning, line)?,
+            have: parse_mask(have, line)?,
+        });
+    }
+    Ok(out)
 }
 
-fn calc_points(matches: usize) -> u64 {
-    if matches == 0 {
-        0
-    } else {
-        2_u64.pow((matches - 1).try_into().unwrap())
+// how many of each card there are once every win has been paid out
+fn copies(cards: &[Card], overrun: Overrun) -> Result<Vec<u128>, CardError> {
+    let mut copies = vec![1u128; cards.len()];
+    for (i, card) in cards.iter().enumerate() {
+        let wins = card.matches();
+        if i + wins >= cards.len() && overrun == Overrun::Fail {
+            return Err(CardError::Overrun { card: i + 1 });
+        }
+        let end = (i + 1 + wins).min(cards.len());
+        for j in i + 1..end {
+            copies[j] = copies[j]
+                .checked_add(copies[i])
+                .ok_or(CardError::Overflow { card: j + 1 })?;
+        }
     }
+    Ok(copies)
 }
 
-fn process1(lines: &Vec<String>) -> u64 {
-    let cards = parse_cards(&lines);
-    let wins = cards
-        .iter()
-        .map(|x| x.0.intersection(&x.1))
-        .collect::<Vec<_>>();
-    wins.into_iter().map(|x| calc_points(x.count())).sum()
+fn process1(lines: &[String]) -> Result<u128, CardError> {
+    let cards = parse_cards(lines)?;
+    let mut total = 0u128;
+    for (i, c) in cards.iter().enumerate() {
+        total = total
+            .checked_add(c.points())
+            .ok_or(CardError::Overflow { card: i + 1 })?;
+    }
+    Ok(total)
 }
 
-fn process2(lines: &Vec<String>) -> u64 {
-    let cards = parse_cards(&lines);
-    let wins = cards
-        .iter()
-        .map(|x| x.0.intersection(&x.1))
-        .collect::<Vec<_>>();
-    let wins = wins.into_iter().map(|x| x.count()).collect::<Vec<_>>();
-    let mut cards = vec![1; wins.len()];
-    for i in 0..wins.len() {
-        for j in 0..wins[i] {
-            cards[i + j + 1] += cards[i];
-        }
+fn process2(lines: &[String], overrun: Overrun) -> Result<u128, CardError> {
+    let copies = copies(&parse_cards(lines)?, overrun)?;
+    let mut total = 0u128;
+    for (i, c) in copies.iter().enumerate() {
+        total = total
+            .checked_add(*c)
+            .ok_or(CardError::Overflow { card: i + 1 })?;
     }
-    cards.iter().sum()
+    Ok(total)
 }
 
 fn main() {
     const INPUT: &str = "input.txt";
     let lines = read_lines(INPUT);
-    let total = process1(&lines);
-    println!("1: {total}");
-    let total = process2(&lines);
-    println!("2: {total}");
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let overrun = if args.iter().any(|x| x == "strict") {
+        Overrun::Fail
+    } else {
+        Overrun::Clamp
+    };
+    if args.iter().any(|x| x == "copies") {
+        match parse_cards(&lines).and_then(|c| copies(&c, overrun)) {
+            Ok(copies) => {
+                for (i, n) in copies.iter().enumerate() {
+                    println!("card {}: {n}", i + 1);
+                }
+            }
+            Err(e) => println!("{e:?}"),
+        }
+    }
+
+    match process1(&lines) {
+        Ok(total) => println!("1: {total}"),
+        Err(e) => println!("1: {e:?}"),
+    }
+    match process2(&lines, overrun) {
+        Ok(total) => println!("2: {total}"),
+        Err(e) => println!("2: {e:?}"),
+    }
 }
 
 #[cfg(test)]
@@ -90,12 +161,14 @@ mod tests {
     Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
     fn const_to_lines(c: &str) -> Vec<String> {
-        c.lines().map(|x| String::from(x)).collect()
+        c.lines().map(String::from).collect()
     }
 
-    fn test_example(t: fn(&Vec<String>) -> u64, expected: u64) {
+    fn test_example(
+        t: fn(&[String]) -> Result<u128, CardError>, expected: u128,
+    ) {
         let lines = const_to_lines(EXAMPLE);
-        assert_eq!(t(&lines), expected);
+        assert_eq!(t(&lines), Ok(expected));
     }
 
     #[test]
@@ -105,6 +178,57 @@ mod tests {
 
     #[test]
     fn example2() {
-        test_example(process2, 30);
+        test_example(|x| process2(x, Overrun::Clamp), 30);
+        test_example(|x| process2(x, Overrun::Fail), 30);
+    }
+
+    #[test]
+    fn test_copies() {
+        let cards = parse_cards(&const_to_lines(EXAMPLE)).unwrap();
+        assert_eq!(cards[0].matches(), 4);
+        assert_eq!(cards[0].points(), 8);
+        let copies = copies(&cards, Overrun::Fail).unwrap();
+        assert_eq!(copies, [1, 2, 4, 8, 14, 1]);
+    }
+
+    #[test]
+    fn test_overrun() {
+        let lines = const_to_lines(
+            "Card 1: 1 2 | 1\n\
+             Card 2: 1 2 | 1 2\n\
+             Card 3: 5 | 5",
+        );
+        let cards = parse_cards(&lines).unwrap();
+        assert_eq!(copies(&cards, Overrun::Clamp), Ok(vec![1, 2, 3]));
+        let err = CardError::Overrun { card: 2 };
+        assert_eq!(copies(&cards, Overrun::Fail), Err(err));
+        assert_eq!(process2(&lines, Overrun::Fail), Err(err));
+    }
+
+    #[test]
+    fn test_overflow() {
+        // every card wins copies of the next ten, so counts grow about
+        // twofold a card
+        let card = "Card 1: 1 2 3 4 5 6 7 8 9 10 | 1 2 3 4 5 6 7 8 9 10";
+        let lines = vec![card.to_string(); 200];
+        let err = process2(&lines, Overrun::Clamp).unwrap_err();
+        assert!(matches!(err, CardError::Overflow { card: 120..=140 }));
+        let total = process2(&lines[..100], Overrun::Clamp).unwrap();
+        assert!(total > u64::MAX as u128);
+
+        let card = (0..128).map(|x| x.to_string()).collect::<Vec<_>>();
+        let card = format!("Card 1: {0} | {0}", card.join(" "));
+        assert_eq!(process1(&[card]), Ok(1 << 127));
+    }
+
+    #[test]
+    fn test_errors() {
+        let check = |s: &str| parse_cards(&const_to_lines(s));
+        assert_eq!(check("Card 1: 1 2 3"), Err(CardError::Format { line: 1 }));
+        assert_eq!(
+            check("Card 1: 1 | 2\nCard 2: 1 | 128"),
+            Err(CardError::Number { line: 2 })
+        );
+        assert_eq!(check("Card 1: x | 2"), Err(CardError::Number { line: 1 }));
     }
 }
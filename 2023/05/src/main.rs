@@ -1,35 +1,69 @@
 use itertools::Itertools;
 use num::One;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use regex::Regex;
-use std::cmp::{min, PartialEq};
-use std::collections::{HashMap, VecDeque};
+use std::cmp::{max, min, PartialEq};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
+use std::fmt;
 use std::fs::read_to_string;
-use std::ops::Add;
+use std::ops::{Add, Range};
 
 struct ParseContext {
     seeds: Regex,
     map: Regex,
 }
 
+// len is u128 so that a piece can run through u64::MAX
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct Diff {
     from: u64,
     to: u64,
-    len: u64,
+    len: u128,
 }
 
+// one past u64::MAX, where every Func ends
+const END: u128 = 1 << 64;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct Map<'a> {
     dst: &'a str,
     diffs: Vec<Diff>,
+    line: usize,
 }
 
+// order holds (src, header line) of every map in the file, including any
+// that repeat a source already in maps
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct MapSet<'a> {
     seeds: Vec<u64>,
     maps: HashMap<&'a str, Map<'a>>,
+    order: Vec<(&'a str, usize)>,
+}
+
+// line numbers start at 1; a map's ranges follow its header line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AlmanacError {
+    NoSeedMap,
+    Duplicate { line: usize, first: usize },
+    Cycle { line: usize },
+    Orphan { line: usize },
+    EmptyRange { line: usize },
+    Overlap { line: usize, other: usize },
+}
+
+// piecewise-linear map over all of u64: the diffs are sorted, contiguous
+// and start at zero, with identity stretches filled in
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Func {
+    pieces: Vec<Diff>,
 }
 
+// the lowest value with zero or several preimages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct NotInvertible(u64);
+
 struct MultiRangeIterator<T> {
     starts: VecDeque<T>,
     stops: VecDeque<T>,
@@ -38,6 +72,7 @@ struct MultiRangeIterator<T> {
     stop: Option<T>,
     calls: u64,
     total: u64,
+    quiet: bool,
 }
 
 impl ParseContext {
@@ -50,29 +85,87 @@ impl ParseContext {
 }
 
 impl<'a> Map<'a> {
-    fn new(dst: &'a str) -> Map<'a> {
+    fn new(dst: &'a str, line: usize) -> Map<'a> {
         Map {
-            dst: dst,
+            dst,
             diffs: Vec::new(),
+            line,
         }
     }
 
     fn add_range(&mut self, from: u64, to: u64, len: u64) {
         self.diffs.push(Diff {
-            from: from,
-            to: to,
-            len: len,
+            from,
+            to,
+            len: len as u128,
         });
     }
 
     fn xlate(&self, num: u64) -> u64 {
         for d in &self.diffs {
-            if num >= d.from && num < d.from + d.len {
+            if num >= d.from && (num as u128) < d.end() {
                 return num - d.from + d.to;
             }
         }
         num
     }
+
+    fn check_ranges(&self, errs: &mut Vec<AlmanacError>) {
+        let mut diffs = Vec::new();
+        for (i, d) in self.diffs.iter().enumerate() {
+            let line = self.line + i + 1;
+            if d.len == 0 {
+                errs.push(AlmanacError::EmptyRange { line });
+            } else {
+                diffs.push((d.from as u128, d.end(), line));
+            }
+        }
+        diffs.sort();
+
+        // the range reaching furthest so far, and its line
+        let mut reach: Option<(u128, usize)> = None;
+        for (from, end, line) in diffs {
+            match reach {
+                Some((r, other)) if from < r => {
+                    errs.push(AlmanacError::Overlap {
+                        line: max(line, other),
+                        other: min(line, other),
+                    });
+                    if end > r {
+                        reach = Some((end, line));
+                    }
+                }
+                _ => reach = Some((end, line)),
+            }
+        }
+    }
+
+    fn func(&self) -> Func {
+        let mut diffs = self.diffs.clone();
+        diffs.sort_by_key(|d| d.from);
+        let mut pieces = Vec::new();
+        let mut at: u128 = 0;
+        for d in diffs {
+            let (from, end) = (d.from as u128, d.end());
+            if end <= at {
+                continue;
+            }
+            if from > at {
+                pieces.push(Diff::same(at as u64, from - at));
+            }
+            let skip = at.saturating_sub(from);
+            pieces.push(Diff {
+                from: d.from + skip as u64,
+                to: d.to + skip as u64,
+                len: d.len - skip,
+            });
+            at = end;
+        }
+        if at < END {
+            pieces.push(Diff::same(at as u64, END - at));
+        }
+        Func::new(pieces)
+    }
 }
 
 impl<'a> MapSet<'a> {
@@ -80,21 +173,244 @@ impl<'a> MapSet<'a> {
         MapSet {
             seeds: Vec::new(),
             maps: HashMap::new(),
+            order: Vec::new(),
         }
     }
 
     fn add(&mut self, src: &'a str, map: Map<'a>) {
-        self.maps.insert(src, map);
+        self.order.push((src, map.line));
+        self.maps.entry(src).or_insert(map);
     }
 
     fn xlate(&self, src: &'a str, num: u64) -> Option<(&'a str, u64)> {
-        match self.maps.get(src) {
-            None => None,
-            Some(map) => Some((map.dst, map.xlate(num))),
+        self.maps.get(src).map(|map| (map.dst, map.xlate(num)))
+    }
+
+    // collapse every map from `src` onward into one function, and return
+    // the category it ends at
+    fn chain(&self, src: &'a str) -> (&'a str, Func) {
+        let mut f = Func::new(vec![Diff::same(0, END)]);
+        let mut src = src;
+        for _ in 0..self.maps.len() {
+            match self.maps.get(src) {
+                None => break,
+                Some(map) => {
+                    f = f.then(&map.func());
+                    src = map.dst;
+                }
+            }
         }
+        (src, f)
+    }
+
+    fn seed_ranges(&self) -> Vec<Range<u64>> {
+        merge(self.seeds.chunks(2).map(|x| x[0]..x[0] + x[1]).collect())
+    }
+
+    fn validate(&self) -> Result<(), Vec<AlmanacError>> {
+        let mut errs = Vec::new();
+
+        let mut first = HashMap::new();
+        let mut dups = HashSet::new();
+        for (src, line) in &self.order {
+            if let Some(f) = first.get(src) {
+                errs.push(AlmanacError::Duplicate {
+                    line: *line,
+                    first: *f,
+                });
+                dups.insert(*line);
+            } else {
+                first.insert(src, *line);
+            }
+        }
+
+        let mut chain = HashSet::new();
+        let mut seen = vec!["seed"];
+        let mut src = "seed";
+        if !self.maps.contains_key(src) {
+            errs.push(AlmanacError::NoSeedMap);
+        }
+        while let Some(map) = self.maps.get(src) {
+            chain.insert(map.line);
+            if seen.contains(&map.dst) {
+                errs.push(AlmanacError::Cycle { line: map.line });
+                break;
+            }
+            seen.push(map.dst);
+            src = map.dst;
+        }
+        for (_, line) in &self.order {
+            if !chain.contains(line) && !dups.contains(line) {
+                errs.push(AlmanacError::Orphan { line: *line });
+            }
+        }
+
+        for map in self.maps.values() {
+            map.check_ranges(&mut errs);
+        }
+
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            errs.sort_by_key(|e| e.line());
+            Err(errs)
+        }
+    }
+}
+
+impl AlmanacError {
+    fn line(&self) -> usize {
+        match self {
+            AlmanacError::NoSeedMap => 0,
+            AlmanacError::Duplicate { line, .. }
+            | AlmanacError::Cycle { line }
+            | AlmanacError::Orphan { line }
+            | AlmanacError::EmptyRange { line }
+            | AlmanacError::Overlap { line, .. } => *line,
+        }
+    }
+}
+
+impl Diff {
+    fn same(from: u64, len: u128) -> Diff {
+        Diff {
+            from,
+            to: from,
+            len,
+        }
+    }
+
+    fn end(&self) -> u128 {
+        self.from as u128 + self.len
     }
 }
 
+impl Func {
+    // drop empty pieces and join neighbors that share an offset
+    fn new(pieces: Vec<Diff>) -> Func {
+        let mut out: Vec<Diff> = Vec::new();
+        for p in pieces.into_iter().filter(|p| p.len > 0) {
+            match out.last_mut() {
+                Some(l)
+                    if l.end() == p.from as u128
+                        && l.to as u128 + l.len == p.to as u128 =>
+                {
+                    l.len += p.len;
+                }
+                _ => out.push(p),
+            }
+        }
+        Func { pieces: out }
+    }
+
+    fn piece(&self, num: u64) -> usize {
+        self.pieces.partition_point(|p| p.end() <= num as u128)
+    }
+
+    fn apply(&self, num: u64) -> u64 {
+        let p = &self.pieces[self.piece(num)];
+        num - p.from + p.to
+    }
+
+    // self, then other
+    fn then(&self, other: &Func) -> Func {
+        let mut pieces = Vec::new();
+        for p in &self.pieces {
+            let (lo, hi) = (p.to as u128, p.to as u128 + p.len);
+            for q in &other.pieces[other.piece(p.to)..] {
+                if q.from as u128 >= hi {
+                    break;
+                }
+                let (s, e) = (max(lo, q.from as u128), min(hi, q.end()));
+                pieces.push(Diff {
+                    from: p.from + (s - lo) as u64,
+                    to: q.to + (s - q.from as u128) as u64,
+                    len: e - s,
+                });
+            }
+        }
+        Func::new(pieces)
+    }
+
+    fn invert(&self) -> Result<Func, NotInvertible> {
+        let mut pieces = self
+            .pieces
+            .iter()
+            .map(|p| Diff {
+                from: p.to,
+                to: p.from,
+                len: p.len,
+            })
+            .collect::<Vec<_>>();
+        pieces.sort_by_key(|p| p.from);
+        let mut at = 0;
+        for p in &pieces {
+            if p.from as u128 != at {
+                return Err(NotInvertible(min(at, p.from as u128) as u64));
+            }
+            at = p.end();
+        }
+        if at != END {
+            return Err(NotInvertible(at as u64));
+        }
+        Ok(Func::new(pieces))
+    }
+
+    // the sorted, merged ranges that `r` maps onto
+    fn image(&self, r: Range<u64>) -> Vec<Range<u64>> {
+        let mut out = Vec::new();
+        if r.is_empty() {
+            return out;
+        }
+        for p in &self.pieces[self.piece(r.start)..] {
+            if p.from >= r.end {
+                break;
+            }
+            let (s, e) = (max(r.start, p.from), min(r.end as u128, p.end()));
+            let e = e as u64;
+            out.push(s - p.from + p.to..e - p.from + p.to);
+        }
+        merge(out)
+    }
+}
+
+impl fmt::Display for Func {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:>20} {:>20} {:>21}", "first", "last", "shift")?;
+        for p in &self.pieces {
+            let shift = p.to as i128 - p.from as i128;
+            let last = p.end() - 1;
+            writeln!(f, "{:>20} {:>20} {:>+21}", p.from, last, shift)?;
+        }
+        Ok(())
+    }
+}
+
+fn merge(mut ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    ranges.sort_by_key(|r| r.start);
+    let mut out: Vec<Range<u64>> = Vec::new();
+    for r in ranges.into_iter().filter(|r| !r.is_empty()) {
+        match out.last_mut() {
+            Some(l) if r.start <= l.end => l.end = max(l.end, r.end),
+            _ => out.push(r),
+        }
+    }
+    out
+}
+
+fn intersect(a: &[Range<u64>], b: &[Range<u64>]) -> Vec<Range<u64>> {
+    let mut out = Vec::new();
+    for x in a {
+        for y in b {
+            let r = max(x.start, y.start)..min(x.end, y.end);
+            if !r.is_empty() {
+                out.push(r);
+            }
+        }
+    }
+    merge(out)
+}
+
 impl<T> MultiRangeIterator<T>
 where
     T: Copy + PartialEq,
@@ -108,6 +424,7 @@ where
             stop: None,
             calls: 0,
             total: 0,
+            quiet: false,
         }
     }
 
@@ -130,7 +447,7 @@ where
     }
 
     fn progress(&self) {
-        if self.calls % 1000000u64 == 0 {
+        if !self.quiet && self.calls.is_multiple_of(1000000) {
             println!("{} - {}%", self.calls, 100 * self.calls / self.total);
         }
     }
@@ -163,24 +480,24 @@ where
     }
 }
 
-fn parse(input: &String) -> MapSet {
-    let mut input = input.lines();
+fn parse(input: &str) -> MapSet<'_> {
+    let mut input = input.lines().enumerate().map(|(i, x)| (i + 1, x));
     let ctx = ParseContext::new();
     let mut set = MapSet::new();
 
-    let seeds = input.next().unwrap();
+    let (_, seeds) = input.next().unwrap();
     let seeds = ctx.seeds.captures(seeds).unwrap().get(1).unwrap().as_str();
     set.seeds = seeds
         .split_whitespace()
         .map(|x| x.parse().unwrap())
         .collect();
-    assert_eq!(input.next().unwrap(), "");
+    assert_eq!(input.next().unwrap().1, "");
 
-    while let Some(line) = input.next() {
+    while let Some((n, line)) = input.next() {
         let (_, [src, dst]) = ctx.map.captures(line).unwrap().extract();
-        let mut map = Map::new(dst);
-        while let Some(line) = input.next() {
-            if line == "" {
+        let mut map = Map::new(dst, n);
+        for (_, line) in input.by_ref() {
+            if line.is_empty() {
                 break;
             }
             let (to, from, len) = line
@@ -196,6 +513,12 @@ fn parse(input: &String) -> MapSet {
     set
 }
 
+fn load(input: &str) -> Result<MapSet<'_>, Vec<AlmanacError>> {
+    let maps = parse(input);
+    maps.validate()?;
+    Ok(maps)
+}
+
 fn run<I>(seeds: I, maps: &MapSet) -> u64
 where
     I: Iterator<Item = u64>,
@@ -220,36 +543,122 @@ where
     best
 }
 
-fn process1(input: &String) -> u64 {
-    let maps = parse(input);
-    run(maps.seeds.clone().into_iter(), &maps)
+fn lowest(seeds: &[Range<u64>], maps: &MapSet) -> u64 {
+    let (_, f) = maps.chain("seed");
+    seeds
+        .iter()
+        .filter_map(|r| f.image(r.clone()).first().map(|x| x.start))
+        .min()
+        .unwrap_or(u64::MAX)
 }
 
-fn process2(input: &String) -> u64 {
-    let maps = parse(input);
+// the seeds in the almanac's ranges that land below location `n`
+fn seeds_below(
+    maps: &MapSet, n: u64,
+) -> Result<Vec<Range<u64>>, NotInvertible> {
+    let (_, f) = maps.chain("seed");
+    let seeds = f.invert()?.image(0..n);
+    Ok(intersect(&seeds, &maps.seed_ranges()))
+}
+
+fn process1(input: &str) -> Result<u64, Vec<AlmanacError>> {
+    let maps = load(input)?;
+    let (_, f) = maps.chain("seed");
+    Ok(maps
+        .seeds
+        .iter()
+        .map(|s| f.apply(*s))
+        .min()
+        .unwrap_or(u64::MAX))
+}
+
+#[cfg(not(feature = "parallel"))]
+fn process2(input: &str) -> Result<u64, Vec<AlmanacError>> {
+    let maps = load(input)?;
     let mut seeds = MultiRangeIterator::new();
     for x in maps.seeds.chunks(2) {
         seeds.add(x[0], x[0] + x[1], x[1]);
     }
-    run(seeds, &maps)
+    Ok(run(seeds, &maps))
+}
+
+#[cfg(feature = "parallel")]
+fn process2(input: &str) -> Result<u64, Vec<AlmanacError>> {
+    let maps = load(input)?;
+    let best = maps
+        .seeds
+        .par_chunks(2)
+        .map(|x| {
+            // progress from every thread at once would interleave at random
+            let mut seeds = MultiRangeIterator::new();
+            seeds.quiet = true;
+            seeds.add(x[0], x[0] + x[1], x[1]);
+            run(seeds, &maps)
+        })
+        .min()
+        .unwrap_or(u64::MAX);
+    Ok(best)
 }
 
 fn main() {
     const INPUT: &str = "input.txt";
     let input = read_to_string(INPUT).unwrap();
-    let total = process1(&input);
-    println!("1: {total}");
-    let total = process2(&input);
-    println!("2: {total}");
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(|x| x.as_str()).collect::<Vec<_>>();
+    if !args.is_empty() {
+        let maps = match load(&input) {
+            Ok(maps) => maps,
+            Err(errs) => return errs.iter().for_each(|e| println!("{e:?}")),
+        };
+        const USAGE: &str = "usage: day05 [table | composed | below N]";
+        match args[..] {
+            ["table"] => {
+                let (dst, f) = maps.chain("seed");
+                println!("seed to {dst}");
+                print!("{f}");
+            }
+            ["composed"] => {
+                println!("2: {}", lowest(&maps.seed_ranges(), &maps));
+            }
+            ["below", n] => {
+                let Ok(n) = n.parse() else {
+                    return println!("{USAGE}");
+                };
+                match seeds_below(&maps, n) {
+                    Ok(seeds) => {
+                        for r in seeds {
+                            println!("{}..{}", r.start, r.end);
+                        }
+                    }
+                    Err(e) => println!("{e:?}"),
+                }
+            }
+            _ => println!("{USAGE}"),
+        }
+        return;
+    }
+
+    match process1(&input) {
+        Ok(total) => println!("1: {total}"),
+        Err(e) => println!("1: {e:?}"),
+    }
+    match process2(&input) {
+        Ok(total) => println!("2: {total}"),
+        Err(e) => println!("2: {e:?}"),
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     const INPUT: &str = "example.txt";
 
-    fn test_example(t: fn(&String) -> u64, expected: u64) {
+    fn test_example(
+        t: fn(&str) -> Result<u64, Vec<AlmanacError>>, expected: u64,
+    ) {
         let input = super::read_to_string(INPUT).unwrap();
-        assert_eq!(t(&input), expected);
+        assert_eq!(t(&input), Ok(expected));
     }
 
     #[test]
@@ -261,4 +670,147 @@ mod tests {
     fn example2() {
         test_example(super::process2, 46);
     }
+
+    fn func(diffs: &[(u64, u64, u64)]) -> Func {
+        let mut map = Map::new("x", 0);
+        for (to, from, len) in diffs {
+            map.add_range(*from, *to, *len);
+        }
+        map.func()
+    }
+
+    #[test]
+    fn test_func() {
+        let mut map = Map::new("soil", 0);
+        map.add_range(98, 50, 2);
+        map.add_range(50, 52, 48);
+        let f = map.func();
+        assert_eq!(
+            f.pieces,
+            [
+                Diff::same(0, 50),
+                Diff {
+                    from: 50,
+                    to: 52,
+                    len: 48
+                },
+                Diff {
+                    from: 98,
+                    to: 50,
+                    len: 2
+                },
+                Diff::same(100, END - 100),
+            ]
+        );
+        for x in 0..200 {
+            assert_eq!(f.apply(x), map.xlate(x));
+        }
+        assert_eq!(f.invert().unwrap().then(&f), func(&[]));
+        assert_eq!(f.then(&f.invert().unwrap()), func(&[]));
+        assert_eq!(f.image(97..101), [50..52, 99..101]);
+    }
+
+    #[test]
+    fn test_not_invertible() {
+        let f = func(&[(10, 0, 5)]);
+        assert_eq!(f.invert(), Err(NotInvertible(0)));
+        let f = func(&[(0, 10, 5)]);
+        assert_eq!(f.invert(), Err(NotInvertible(0)));
+        let f = func(&[(0, 10, 5), (10, 0, 5)]);
+        assert!(f.invert().is_ok());
+    }
+
+    #[test]
+    fn test_max() {
+        const MAX: u64 = u64::MAX;
+        assert_eq!(func(&[]).pieces, [Diff::same(0, END)]);
+        let f = func(&[(0, 10, 5)]);
+        assert_eq!(f.apply(MAX), MAX);
+        let input = "seeds: 18446744073709551615 11\n\n\
+            seed-to-soil map:\n0 10 5\n";
+        assert_eq!(process1(input), Ok(1));
+        let maps = parse(input);
+        assert_eq!(run([MAX].into_iter(), &maps), MAX);
+
+        // everything moves up one and the top wraps around to 0
+        let f = func(&[(0, MAX, 1), (1, 0, MAX)]);
+        assert_eq!(f.apply(MAX), 0);
+        assert_eq!(f.apply(MAX - 1), MAX);
+        assert_eq!(f.then(&f).apply(MAX), 1);
+        let inv = f.invert().unwrap();
+        assert_eq!(inv.apply(0), MAX);
+        assert_eq!(inv.apply(MAX), MAX - 1);
+        assert_eq!(f.then(&inv), func(&[]));
+        assert_eq!(inv.then(&f), func(&[]));
+        let f = func(&[(5, MAX, 1)]);
+        assert_eq!(f.invert(), Err(NotInvertible(5)));
+    }
+
+    #[test]
+    fn test_chain() {
+        let input = read_to_string(INPUT).unwrap();
+        let maps = parse(&input);
+        let (dst, f) = maps.chain("seed");
+        assert_eq!(dst, "location");
+        for s in 0..120 {
+            assert_eq!(Some(f.apply(s)), Some(run(std::iter::once(s), &maps)));
+        }
+        assert_eq!(lowest(&maps.seed_ranges(), &maps), 46);
+        let inv = f.invert().unwrap();
+        assert_eq!(inv.apply(46), 82);
+        let below = seeds_below(&maps, 60).unwrap();
+        assert_eq!(below, [62..66, 82..92]);
+        for r in maps.seed_ranges() {
+            for s in r {
+                let hit = below.iter().any(|b| b.contains(&s));
+                assert_eq!(hit, f.apply(s) < 60, "{s}");
+            }
+        }
+        assert_eq!(f.to_string().lines().count(), f.pieces.len() + 1);
+    }
+
+    fn check(input: &str) -> Result<(), Vec<AlmanacError>> {
+        parse(input).validate()
+    }
+
+    #[test]
+    fn test_validate() {
+        let input = read_to_string(INPUT).unwrap();
+        assert_eq!(check(&input), Ok(()));
+
+        let input = "seeds: 1\n\n\
+            seed-to-soil map:\n1 2 3\n\n\
+            soil-to-water map:\n1 2 3\n\n\
+            water-to-seed map:\n1 2 3\n\n\
+            light-to-heat map:\n1 2 3";
+        assert_eq!(
+            check(input),
+            Err(vec![
+                AlmanacError::Cycle { line: 9 },
+                AlmanacError::Orphan { line: 12 }
+            ])
+        );
+
+        let input = "seeds: 1\n\n\
+            seed-to-soil map:\n0 10 5\n20 30 0\n0 14 3\n50 0 10\n\n\
+            soil-to-water map:\n1 2 3\n\n\
+            soil-to-light map:\n1 2 3";
+        assert_eq!(
+            check(input),
+            Err(vec![
+                AlmanacError::EmptyRange { line: 5 },
+                AlmanacError::Overlap { line: 6, other: 4 },
+                AlmanacError::Duplicate { line: 12, first: 9 }
+            ])
+        );
+
+        let input = "seeds: 1\n\nsoil-to-water map:\n1 2 3";
+        assert_eq!(
+            check(input),
+            Err(vec![
+                AlmanacError::NoSeedMap,
+                AlmanacError::Orphan { line: 3 }
+            ])
+        );
+    }
 }
@@ -1,18 +1,22 @@
+mod poker;
+
 use itertools::Itertools;
+use poker::{best, parse_cards, rank_of, CardError, Rank};
 use std::cmp::Ordering;
+use std::env;
 use std::fs::read_to_string;
 
 const CARDS: usize = 5;
 
 #[derive(Debug, Copy, Clone, Eq)]
-struct Hand {
-    cards: [u8; CARDS],
+struct Hand<const N: usize = CARDS> {
+    cards: [u8; N],
     wilds: usize,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-struct Game {
-    hand: Hand,
+struct Game<const N: usize = CARDS> {
+    hand: Hand<N>,
     bid: usize,
 }
 
@@ -27,144 +31,77 @@ enum HandType {
     FiveKind,
 }
 
-impl Game {
-    fn new(hand: &str, bid: usize) -> Game {
+// rank of a card once it has been made wild, below every natural card
+const WILD: u8 = 1;
+const JACK: u8 = 11;
+
+impl<const N: usize> Game<N> {
+    fn new(hand: &str, bid: usize) -> Result<Game<N>, CardError> {
         let mut g = Game {
-            hand: Hand { cards: [0; CARDS], wilds: 0, },
-            bid: bid,
+            hand: Hand { cards: [0; N], wilds: 0, },
+            bid,
         };
-        assert_eq!(hand.len(), CARDS);
-        let hand = hand.chars().collect::<Vec<_>>();
-        for i in 0..CARDS {
-            g.hand.cards[i] = match hand[i] {
-                'A' => 14,
-                'K' => 13,
-                'Q' => 12,
-                'J' => 11,
-                'T' => 10,
-                '9' => 9,
-                '8' => 8,
-                '7' => 7,
-                '6' => 6,
-                '5' => 5,
-                '4' => 4,
-                '3' => 3,
-                '2' => 2,
-                _ => panic!("unknown card"),
-            };
+        if hand.chars().count() != N {
+            return Err(CardError::Count(hand.chars().count()));
         }
-        g
-    }
-}
-
-impl Hand {
-    // only for hand type; sorted low to high, unlike poker
-    fn canonicalize(&self) -> [u8; CARDS] {
-        let mut res = self.cards;
-        res.sort();
-        res
-    }
-
-    fn hand_has(&self, card: u8, count: usize) -> bool {
-        card != 1 && self.cards.iter().filter(|x| **x == card).count() == count
-    }
-
-    fn hand_is(&self, count: usize, a: &[u8; CARDS]) -> bool {
-        for i in 0..CARDS {
-            if self.hand_has(a[i], count) {
-                return true;
-            }
+        for (card, c) in g.hand.cards.iter_mut().zip(hand.chars()) {
+            *card = rank_of(c).ok_or(CardError::Rank(c))?;
         }
-        false
-    }
-
-    fn hand_is_five(&self, a: &[u8; CARDS]) -> bool {
-        self.hand_is(5, a)
-    }
-
-    fn hand_is_four(&self, a: &[u8; CARDS]) -> bool {
-        self.hand_is(4, a)
-    }
-
-    fn hand_is_full(&self, a: &[u8; CARDS]) -> bool {
-        self.hand_is_three(a) && self.hand_is_pair(a)
-    }
-
-    fn hand_is_three(&self, a: &[u8; CARDS]) -> bool {
-        self.hand_is(3, a)
+        Ok(g)
     }
+}
 
-    fn hand_is_pair(&self, a: &[u8; CARDS]) -> bool {
-        for i in 0..CARDS - 1 {
-            if self.hand_has(a[i], 2) {
-                return true;
-            }
+impl<const N: usize> Hand<N> {
+    // how many of each natural rank, largest first, with the wilds joining
+    // the largest group
+    fn histogram(&self) -> Vec<usize> {
+        let mut counts = [0; 15];
+        for c in self.cards.iter().filter(|c| **c != WILD) {
+            counts[*c as usize] += 1;
         }
-        false
-    }
-
-    fn hand_is_two_pair(&self, a: &[u8; CARDS]) -> bool {
-        let mut pairs = 0;
-        for i in 0..CARDS - 1 {
-            if a[i] == a[i + 1] && a[i] != 1 {
-                pairs += 1;
-            }
+        let mut h = counts.into_iter().filter(|c| *c > 0).collect::<Vec<_>>();
+        h.sort_by(|a, b| b.cmp(a));
+        match h.first_mut() {
+            Some(c) => *c += self.wilds,
+            None => h.push(self.wilds),
         }
-        pairs == 2
+        h
     }
 
     fn hand_type(&self) -> HandType {
-        let a = self.canonicalize();
-        let mut t = if self.hand_is_five(&a) {
-            HandType::FiveKind
-        } else if self.hand_is_four(&a) {
-            HandType::FourKind
-        } else if self.hand_is_full(&a) {
-            HandType::FullHouse
-        } else if self.hand_is_three(&a) {
-            HandType::ThreeKind
-        } else if self.hand_is_two_pair(&a) {
-            HandType::TwoPair
-        } else if self.hand_is_pair(&a) {
-            HandType::OnePair
-        } else {
-            HandType::HighCard
-        };
-        for _ in 0..self.wilds {
-            t = match t {
-                HandType::HighCard => HandType::OnePair,
-                HandType::OnePair => HandType::ThreeKind,
-                HandType::TwoPair => HandType::FullHouse,
-                HandType::ThreeKind => HandType::FourKind,
-                _ => HandType::FiveKind,
-            };
+        let h = self.histogram();
+        match (h[0], h.get(1).copied().unwrap_or(0)) {
+            (5.., _) => HandType::FiveKind,
+            (4, _) => HandType::FourKind,
+            (3, 2..) => HandType::FullHouse,
+            (3, _) => HandType::ThreeKind,
+            (2, 2) => HandType::TwoPair,
+            (2, _) => HandType::OnePair,
+            _ => HandType::HighCard,
         }
-        t
     }
 
-    fn wild(&mut self) {
-        for i in 0..CARDS {
-            if self.cards[i] == 11 {
-                self.wilds += 1;
-                self.cards[i] = 1;
-            }
+    fn wild_ranks(&mut self, ranks: &[u8]) {
+        for c in self.cards.iter_mut().filter(|c| ranks.contains(c)) {
+            self.wilds += 1;
+            *c = WILD;
         }
     }
 }
 
-impl PartialEq for Hand {
+impl<const N: usize> PartialEq for Hand<N> {
     fn eq(&self, other: &Self) -> bool {
         self.cmp(other) == Ordering::Equal
     }
 }
 
-impl PartialOrd for Hand {
+impl<const N: usize> PartialOrd for Hand<N> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Ord for Hand {
+impl<const N: usize> Ord for Hand<N> {
     fn cmp(&self, other: &Self) -> Ordering {
         let t = self.hand_type().cmp(&other.hand_type());
         if t != Ordering::Equal {
@@ -175,40 +112,92 @@ impl Ord for Hand {
     }
 }
 
-fn parse(input: &String) -> Vec<Game> {
+fn parse<const N: usize>(input: &str) -> Result<Vec<Game<N>>, CardError> {
     let mut games = Vec::new();
 
     for line in input.lines() {
-        let (hand, bid) = line.split_whitespace().collect_tuple().unwrap();
-        let bid = bid.parse::<usize>().unwrap();
-        games.push(Game::new(hand, bid));
+        let (hand, bid) = line
+            .split_whitespace()
+            .collect_tuple()
+            .ok_or(CardError::Missing)?;
+        let bid = bid.parse::<usize>().map_err(|_| CardError::Bid)?;
+        games.push(Game::new(hand, bid)?);
     }
 
-    games
+    Ok(games)
 }
 
-fn process1(input: &String) -> usize {
-    let mut games = parse(input);
+fn score<const N: usize>(mut games: Vec<Game<N>>) -> usize {
     games.sort();
     games.iter().enumerate().fold(0, |x, (i, g)| x + (i + 1) * g.bid)
 }
 
-fn process2(input: &String) -> usize {
-    let mut games = parse(input);
+fn winnings<const N: usize>(
+    input: &str, wilds: &[u8],
+) -> Result<usize, CardError> {
+    let mut games = parse::<N>(input)?;
     for g in games.iter_mut() {
-        g.hand.wild();
+        g.hand.wild_ranks(wilds);
+    }
+    Ok(score(games))
+}
+
+// each line holds five or more suited cards and then the bid, like
+// "AS KD QH JC TS 765"; a hand plays its best five cards
+fn poker_winnings(input: &str) -> Result<usize, CardError> {
+    let mut games: Vec<(Rank, usize)> = Vec::new();
+    for line in input.lines() {
+        let (cards, bid) = line.rsplit_once(' ').ok_or(CardError::Missing)?;
+        let bid = bid.parse::<usize>().map_err(|_| CardError::Bid)?;
+        let cards = parse_cards(cards)?;
+        let (_, rank) = best(&cards).ok_or(CardError::Count(cards.len()))?;
+        games.push((rank, bid));
     }
     games.sort();
-    games.iter().enumerate().fold(0, |x, (i, g)| x + (i + 1) * g.bid)
+    Ok(games.iter().enumerate().fold(0, |x, (i, g)| x + (i + 1) * g.1))
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Rules<'a> {
+    // five unsuited cards, with the given ranks wild
+    Camel(&'a [u8]),
+    Poker,
+}
+
+fn play(input: &str, rules: Rules) -> Result<usize, CardError> {
+    match rules {
+        Rules::Camel(wilds) => winnings::<CARDS>(input, wilds),
+        Rules::Poker => poker_winnings(input),
+    }
+}
+
+fn process1(input: &str) -> Result<usize, CardError> {
+    play(input, Rules::Camel(&[]))
+}
+
+fn process2(input: &str) -> Result<usize, CardError> {
+    play(input, Rules::Camel(&[JACK]))
 }
 
 fn main() {
     const INPUT: &str = "input.txt";
     let input = read_to_string(INPUT).unwrap();
-    let total = process1(&input);
-    println!("1: {total}");
-    let total = process2(&input);
-    println!("2: {total}");
+    if env::args().nth(1).as_deref() == Some("poker") {
+        match play(&input, Rules::Poker) {
+            Ok(total) => println!("poker: {total}"),
+            Err(e) => println!("poker: {e:?}"),
+        }
+        return;
+    }
+
+    match process1(&input) {
+        Ok(total) => println!("1: {total}"),
+        Err(e) => println!("1: {e:?}"),
+    }
+    match process2(&input) {
+        Ok(total) => println!("2: {total}"),
+        Err(e) => println!("2: {e:?}"),
+    }
 }
 
 #[cfg(test)]
@@ -217,9 +206,9 @@ mod tests {
 
     const INPUT: &str = "example.txt";
 
-    fn test_example(t: fn(&String) -> usize, expected: usize) {
+    fn test_example(t: fn(&str) -> Result<usize, CardError>, expected: usize) {
         let input = super::read_to_string(INPUT).unwrap();
-        assert_eq!(t(&input), expected);
+        assert_eq!(t(&input), Ok(expected));
     }
 
     #[test]
@@ -267,19 +256,19 @@ mod tests {
         let mut x = Hand {
             cards: [7, 7, 6, 7, 11], wilds: 0,
         };
-        x.wild();
+        x.wild_ranks(&[JACK]);
         assert_eq!(x.cards, [7, 7, 6, 7, 1]);
         assert_eq!(x.wilds, 1);
         assert_eq!(x.hand_type(), HandType::FourKind);
         let mut x = Hand {
             cards: [11, 11, 11, 11, 11], wilds: 0,
         };
-        x.wild();
+        x.wild_ranks(&[JACK]);
         assert_eq!(x.hand_type(), HandType::FiveKind);
         let mut x = Hand {
             cards: [10, 13, 11, 13, 7], wilds: 0,
         };
-        x.wild();
+        x.wild_ranks(&[JACK]);
         assert_eq!(x.hand_type(), HandType::ThreeKind);
         let x = Hand {
             cards: [14, 12, 12, 1, 1], wilds: 2,
@@ -287,6 +276,71 @@ mod tests {
         assert_eq!(x.hand_type(), HandType::FourKind);
     }
 
+    #[test]
+    fn test_histogram() {
+        let x = Hand {
+            cards: [9, 9, 2, 3, 9, 9, 2], wilds: 0,
+        };
+        assert_eq!(x.histogram(), [4, 2, 1]);
+        assert_eq!(x.hand_type(), HandType::FourKind);
+        let x = Hand {
+            cards: [1, 1, 1, 1, 1], wilds: 5,
+        };
+        assert_eq!(x.histogram(), [5]);
+        let x = Hand {
+            cards: [2, 3], wilds: 0,
+        };
+        assert_eq!(x.hand_type(), HandType::HighCard);
+        let x = Hand {
+            cards: [8, 8, 7, 7, 6, 6, 5], wilds: 0,
+        };
+        assert_eq!(x.hand_type(), HandType::TwoPair);
+    }
+
+    #[test]
+    fn test_wild_ranks() {
+        let mut x = Hand {
+            cards: [2, 3, 13, 4, 5], wilds: 0,
+        };
+        x.wild_ranks(&[2, 3]);
+        assert_eq!(x.cards, [1, 1, 13, 4, 5]);
+        assert_eq!(x.hand_type(), HandType::ThreeKind);
+        let mut x = Hand {
+            cards: [2, 3, 3, 4, 4, 9], wilds: 0,
+        };
+        x.wild_ranks(&[9]);
+        assert_eq!(x.hand_type(), HandType::FullHouse);
+
+        let input = super::read_to_string(INPUT).unwrap();
+        assert_eq!(winnings::<5>(&input, &[]), process1(&input));
+        assert_eq!(winnings::<5>(&input, &[13]), Ok(4602));
+        assert_eq!(winnings::<5>(&input, &[JACK]), process2(&input));
+    }
+
+    #[test]
+    fn test_rules() {
+        let input = super::read_to_string(INPUT).unwrap();
+        assert_eq!(play(&input, Rules::Camel(&[JACK])), process2(&input));
+        assert_eq!(
+            play("32T3K 765\nT55X5 684", Rules::Camel(&[])),
+            Err(CardError::Rank('X'))
+        );
+        assert_eq!(parse::<5>("32T3 765"), Err(CardError::Count(4)));
+        assert_eq!(parse::<5>("32T3K"), Err(CardError::Missing));
+        assert_eq!(parse::<5>("32T3K x"), Err(CardError::Bid));
+
+        let input = "\
+            2H 2D 9C 9S KH 10\n\
+            AH 2D 3C 4S 5H 100\n\
+            2H 3D 4C 5S 6H 1000\n\
+            AS KS QS JS 9S 8D 7C 1";
+        assert_eq!(play(input, Rules::Poker), Ok(10 + 2 * 100 + 3 * 1000 + 4));
+        assert_eq!(
+            play("AS KS QS JS 1", Rules::Poker),
+            Err(CardError::Count(4))
+        );
+    }
+
     #[test]
     fn example1() {
         test_example(process1, 6440);
@@ -0,0 +1,239 @@
+use itertools::Itertools;
+use std::str::FromStr;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+}
+
+// rank runs from 2 up to 14 for the ace
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Card {
+    pub rank: u8,
+    pub suit: Suit,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CardError {
+    Rank(char),
+    Suit(char),
+    Missing,
+    Count(usize),
+    Duplicate(Card),
+    Bid,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourKind,
+    StraightFlush,
+}
+
+// ties within a category are broken by `ranks`, most significant first
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Rank {
+    pub category: Category,
+    pub ranks: Vec<u8>,
+}
+
+pub fn rank_of(c: char) -> Option<u8> {
+    match c {
+        'A' => Some(14),
+        'K' => Some(13),
+        'Q' => Some(12),
+        'J' => Some(11),
+        'T' => Some(10),
+        '2'..='9' => Some(c as u8 - b'0'),
+        _ => None,
+    }
+}
+
+impl FromStr for Card {
+    type Err = CardError;
+
+    fn from_str(s: &str) -> Result<Card, CardError> {
+        let mut chars = s.chars();
+        let (r, s) = match (chars.next(), chars.next(), chars.next()) {
+            (Some(r), Some(s), None) => (r, s),
+            _ => return Err(CardError::Missing),
+        };
+        let rank = rank_of(r).ok_or(CardError::Rank(r))?;
+        let suit = match s {
+            'C' => Suit::Clubs,
+            'D' => Suit::Diamonds,
+            'H' => Suit::Hearts,
+            'S' => Suit::Spades,
+            _ => return Err(CardError::Suit(s)),
+        };
+        Ok(Card { rank, suit })
+    }
+}
+
+// cards separated by whitespace, like "AS KD"; no card may repeat
+pub fn parse_cards(s: &str) -> Result<Vec<Card>, CardError> {
+    let mut cards = Vec::new();
+    for c in s.split_whitespace() {
+        let c = c.parse()?;
+        if cards.contains(&c) {
+            return Err(CardError::Duplicate(c));
+        }
+        cards.push(c);
+    }
+    Ok(cards)
+}
+
+// the top card of a straight, with the ace playing low in A-2-3-4-5
+fn straight(ranks: &[u8]) -> Option<u8> {
+    match ranks {
+        [14, 5, 4, 3, 2] => Some(5),
+        [a, .., e] if a - e == 4 && ranks.iter().all_unique() => Some(*a),
+        _ => None,
+    }
+}
+
+pub fn rank5(cards: &[Card; 5]) -> Rank {
+    let mut ranks = cards.iter().map(|c| c.rank).collect::<Vec<_>>();
+    ranks.sort_by(|a, b| b.cmp(a));
+    let flush = cards.iter().map(|c| c.suit).all_equal();
+
+    if let Some(high) = straight(&ranks) {
+        let category = if flush {
+            Category::StraightFlush
+        } else {
+            Category::Straight
+        };
+        return Rank {
+            category,
+            ranks: vec![high],
+        };
+    }
+
+    // groups of equal rank, biggest group first, then highest rank
+    let mut groups = ranks
+        .iter()
+        .dedup_with_count()
+        .map(|(n, r)| (n, *r))
+        .collect::<Vec<_>>();
+    groups.sort_by(|a, b| b.cmp(a));
+    let category = match (groups[0].0, groups.get(1).map(|g| g.0)) {
+        (4, _) => Category::FourKind,
+        (3, Some(2)) => Category::FullHouse,
+        _ if flush => Category::Flush,
+        (3, _) => Category::ThreeKind,
+        (2, Some(2)) => Category::TwoPair,
+        (2, _) => Category::OnePair,
+        _ => Category::HighCard,
+    };
+    Rank {
+        category,
+        ranks: groups.into_iter().map(|g| g.1).collect(),
+    }
+}
+
+// the best five cards out of five or more
+pub fn best(cards: &[Card]) -> Option<([Card; 5], Rank)> {
+    cards
+        .iter()
+        .copied()
+        .combinations(5)
+        .map(|h| {
+            let h: [Card; 5] = h.try_into().unwrap();
+            (h, rank5(&h))
+        })
+        .max_by(|a, b| a.1.cmp(&b.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rank(s: &str) -> Rank {
+        let cards = parse_cards(s).unwrap();
+        rank5(&cards.try_into().unwrap())
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_cards("AS KD 2c"), Err(CardError::Suit('c')));
+        let cards = parse_cards("AS KD").unwrap();
+        assert_eq!(
+            cards,
+            [
+                Card {
+                    rank: 14,
+                    suit: Suit::Spades
+                },
+                Card {
+                    rank: 13,
+                    suit: Suit::Diamonds
+                }
+            ]
+        );
+        assert_eq!(parse_cards("1H"), Err(CardError::Rank('1')));
+        assert_eq!(parse_cards("10H"), Err(CardError::Missing));
+        let ace = cards[0];
+        assert_eq!(parse_cards("AS 2S AS"), Err(CardError::Duplicate(ace)));
+    }
+
+    #[test]
+    fn test_categories() {
+        let cases = [
+            ("2H 5D 9C JS KH", Category::HighCard),
+            ("2H 2D 9C JS KH", Category::OnePair),
+            ("2H 2D 9C 9S KH", Category::TwoPair),
+            ("2H 2D 2C JS KH", Category::ThreeKind),
+            ("9H TD JC QS KH", Category::Straight),
+            ("AH 2D 3C 4S 5H", Category::Straight),
+            ("2H 5H 9H JH KH", Category::Flush),
+            ("2H 2D 2C KS KH", Category::FullHouse),
+            ("2H 2D 2C 2S KH", Category::FourKind),
+            ("TS JS QS KS AS", Category::StraightFlush),
+        ];
+        for (hand, category) in cases {
+            assert_eq!(rank(hand).category, category, "{hand}");
+        }
+        assert_eq!(rank("QH KD AC 2S 3H").category, Category::HighCard);
+    }
+
+    #[test]
+    fn test_kickers() {
+        // ace-low is the lowest straight
+        assert!(rank("AH 2D 3C 4S 5H") < rank("2H 3D 4C 5S 6H"));
+        assert!(rank("AH KD QC JS TH") > rank("KH QD JC TS 9H"));
+        assert!(rank("9H 9D AC 5S 2H") > rank("9C 9S KC QS JH"));
+        assert!(rank("9H 9D AC 5S 3H") > rank("9C 9S AD 5C 2H"));
+        assert!(rank("3H 3D 3C 2S 2H") > rank("2C 2D 2S AS AH"));
+        assert!(rank("KH KD 2C 2S 5H") > rank("QC QS JC JS AH"));
+        assert!(rank("KH KD 2C 2S 5H") < rank("KC KS 3C 3S 4H"));
+        assert_eq!(rank("KH KD 2C 2S 5H"), rank("KC KS 2D 2H 5D"));
+        assert_eq!(rank("KH KD 2C 2S 5H").ranks, [13, 2, 5]);
+    }
+
+    #[test]
+    fn test_best() {
+        let cards = parse_cards("2H 8H 9H TH JH QH KD").unwrap();
+        let (hand, r) = best(&cards).unwrap();
+        assert_eq!(r.category, Category::StraightFlush);
+        assert_eq!(r.ranks, [12]);
+        assert!(hand.iter().all(|c| c.suit == Suit::Hearts));
+
+        let cards = parse_cards("AH AD AC KS KH 2C 3D").unwrap();
+        let (_, r) = best(&cards).unwrap();
+        assert_eq!(r.category, Category::FullHouse);
+        assert_eq!(r.ranks, [14, 13]);
+
+        let cards = parse_cards("AH 2D 3C 4S 5H 6D 9C").unwrap();
+        assert_eq!(best(&cards).unwrap().1.ranks, [6]);
+        assert_eq!(best(&cards[..4]), None);
+    }
+}
@@ -1,56 +1,130 @@
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::env;
 use std::fs::read_to_string;
 
-fn parse(input: &String) -> Vec<Vec<i64>> {
+fn parse(input: &str) -> Vec<Vec<i64>> {
     input
         .lines()
         .map(|x| x.split_whitespace().map(|y| y.parse().unwrap()).collect())
         .collect()
 }
 
-fn extrapolate(m: &Vec<i64>, rev: bool) -> i64 {
-    let mut seqs = Vec::new();
-    let mut m = m.clone();
-    if rev {
-        m.reverse();
-    }
-    seqs.push(m);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HistoryError {
+    Empty,
+    NotPolynomial,
+    Overflow,
+}
+
+// newton form on the binomial basis: p(x) = sum of diffs[k] * C(x, k), where
+// diffs[k] is the kth forward difference at index 0.  C(x, k) is an integer
+// for every integer x, so the rational coefficients never need a denominator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Poly {
+    diffs: Vec<i64>,
+}
 
-    while !seqs.last().unwrap().iter().all(|x| *x == 0) {
-        let mut s = Vec::new();
-        let last = seqs.last().unwrap();
-        for i in 0..last.len() - 1 {
-            s.push(last[i + 1] - last[i]);
+impl Poly {
+    // needs a row of the difference pyramid to come out all zeros
+    fn fit(m: &[i64]) -> Result<Poly, HistoryError> {
+        if m.is_empty() {
+            return Err(HistoryError::Empty);
         }
-        seqs.push(s);
+        let mut diffs = Vec::new();
+        let mut row = m.to_vec();
+        while !row.iter().all(|x| *x == 0) {
+            if row.len() == 1 {
+                return Err(HistoryError::NotPolynomial);
+            }
+            diffs.push(row[0]);
+            row = row
+                .windows(2)
+                .map(|w| w[1].checked_sub(w[0]).ok_or(HistoryError::Overflow))
+                .collect::<Result<_, _>>()?;
+        }
+        if diffs.is_empty() {
+            diffs.push(0);
+        }
+        Ok(Poly { diffs })
     }
 
-    let mut new = Vec::new();
-    new.push(0);
-    for i in (1..seqs.len()).rev() {
-        let a = &seqs[i - 1];
-        new.push(new[new.len() - 1] + a[a.len() - 1]);
+    fn degree(&self) -> usize {
+        self.diffs.len() - 1
     }
 
-    new[new.len() - 1]
+    fn at(&self, x: i64) -> Result<i64, HistoryError> {
+        let x = x as i128;
+        let mut total = 0i128;
+        let mut binom = 1i128;
+        for (k, d) in self.diffs.iter().enumerate() {
+            if k > 0 {
+                // exact: binom * (x - k + 1) holds k consecutive factors
+                binom = binom
+                    .checked_mul(x - k as i128 + 1)
+                    .ok_or(HistoryError::Overflow)?
+                    / k as i128;
+            }
+            total = binom
+                .checked_mul(*d as i128)
+                .and_then(|t| t.checked_add(total))
+                .ok_or(HistoryError::Overflow)?;
+        }
+        total.try_into().map_err(|_| HistoryError::Overflow)
+    }
 }
 
-fn process1(input: &String) -> i64 {
-    let metrics = parse(input);
-    metrics.iter().map(|x| extrapolate(x, false)).sum()
+// the value `steps` past the end, or before the start if negative
+fn extrapolate(m: &[i64], steps: i64) -> Result<i64, HistoryError> {
+    let p = Poly::fit(m)?;
+    if steps < 0 {
+        p.at(steps)
+    } else {
+        p.at(m.len() as i64 - 1 + steps)
+    }
 }
 
-fn process2(input: &String) -> i64 {
+fn extrapolate_all(input: &str, steps: i64) -> Result<i64, HistoryError> {
     let metrics = parse(input);
-    metrics.iter().map(|x| extrapolate(x, true)).sum()
+    #[cfg(feature = "parallel")]
+    let metrics = metrics.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let metrics = metrics.iter();
+    metrics.map(|x| extrapolate(x, steps)).sum()
+}
+
+fn process1(input: &str) -> Result<i64, HistoryError> {
+    extrapolate_all(input, 1)
+}
+
+fn process2(input: &str) -> Result<i64, HistoryError> {
+    extrapolate_all(input, -1)
 }
 
 fn main() {
     const INPUT: &str = "input.txt";
     let input = read_to_string(INPUT).unwrap();
-    let total = process1(&input);
-    println!("1: {total}");
-    let total = process2(&input);
-    println!("2: {total}");
+    if let Some(x) = env::args().nth(1) {
+        let Ok(x) = x.parse() else {
+            return println!("usage: day09 [X]");
+        };
+        for (i, m) in parse(&input).iter().enumerate() {
+            match Poly::fit(m).and_then(|p| Ok((p.degree(), p.at(x)?))) {
+                Ok((d, y)) => println!("{i}: degree {d}, p({x}) = {y}"),
+                Err(e) => println!("{i}: {e:?}"),
+            }
+        }
+        return;
+    }
+
+    match process1(&input) {
+        Ok(total) => println!("1: {total}"),
+        Err(e) => println!("1: {e:?}"),
+    }
+    match process2(&input) {
+        Ok(total) => println!("2: {total}"),
+        Err(e) => println!("2: {e:?}"),
+    }
 }
 
 #[cfg(test)]
@@ -59,9 +133,9 @@ mod tests {
 
     const INPUT: &str = "example.txt";
 
-    fn test_example(t: fn(&String) -> i64, expected: i64) {
+    fn test_example(t: fn(&str) -> Result<i64, HistoryError>, expected: i64) {
         let input = super::read_to_string(INPUT).unwrap();
-        assert_eq!(t(&input), expected);
+        assert_eq!(t(&input), Ok(expected));
     }
 
     #[test]
@@ -73,4 +147,39 @@ mod tests {
     fn example2() {
         test_example(process2, 2);
     }
+
+    #[test]
+    fn test_poly() {
+        let p = Poly::fit(&[10, 13, 16, 21, 30, 45]).unwrap();
+        assert_eq!(p.degree(), 3);
+        assert_eq!(p.at(6), Ok(68));
+        assert_eq!(p.at(-1), Ok(5));
+        assert_eq!(Poly::fit(&[7, 7, 7]).unwrap().degree(), 0);
+        assert_eq!(Poly::fit(&[0, 0]).unwrap().at(5), Ok(0));
+
+        // x^2, far in both directions
+        let p = Poly::fit(&[0, 1, 4, 9]).unwrap();
+        assert_eq!(p.at(1_000_000), Ok(1_000_000_000_000));
+        assert_eq!(p.at(-1_000_000), Ok(1_000_000_000_000));
+        assert_eq!(extrapolate(&[0, 1, 4, 9], 3), Ok(36));
+        assert_eq!(extrapolate(&[0, 1, 4, 9], -3), Ok(9));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(Poly::fit(&[]), Err(HistoryError::Empty));
+        assert_eq!(Poly::fit(&[1, 2, 4, 8]), Err(HistoryError::NotPolynomial));
+        assert_eq!(Poly::fit(&[5]), Err(HistoryError::NotPolynomial));
+        assert_eq!(
+            Poly::fit(&[i64::MIN, i64::MAX]),
+            Err(HistoryError::Overflow)
+        );
+        let m = i64::MAX / 2;
+        let p = Poly::fit(&[0, m, 2 * m]).unwrap();
+        assert_eq!(p.at(3), Err(HistoryError::Overflow));
+        assert_eq!(
+            process1("1 2 3\n1 2 4 8\n"),
+            Err(HistoryError::NotPolynomial)
+        );
+    }
 }
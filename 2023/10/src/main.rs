@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::env;
 use std::fs::read_to_string;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -21,34 +22,63 @@ enum Dir {
     Right,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum StartError {
+    Missing,
+    DeadEnd,
+    Ambiguous(Vec<Pipe>),
+}
+
+const DIRS: [Dir; 4] = [Dir::Up, Dir::Down, Dir::Left, Dir::Right];
+const PIPES: [Pipe; 6] = [
+    Pipe::Vert,
+    Pipe::Horiz,
+    Pipe::BendL,
+    Pipe::BendJ,
+    Pipe::Bend7,
+    Pipe::BendF,
+];
+
 impl Pipe {
     fn is_corner(&self) -> bool {
-        match self {
-            Pipe::BendL | Pipe::BendJ | Pipe::Bend7 | Pipe::BendF => true,
-            _ => false,
-        }
+        matches!(self, Pipe::BendL | Pipe::BendJ | Pipe::Bend7 | Pipe::BendF)
     }
 
     fn is_run_diag(a: Pipe, b: Pipe) -> bool {
-        match (a, b) {
+        matches!(
+            (a, b),
             (Pipe::BendF, Pipe::BendJ)
-            | (Pipe::BendJ, Pipe::BendF)
-            | (Pipe::BendL, Pipe::Bend7)
-            | (Pipe::Bend7, Pipe::BendL) => true,
-            _ => false,
+                | (Pipe::BendJ, Pipe::BendF)
+                | (Pipe::BendL, Pipe::Bend7)
+                | (Pipe::Bend7, Pipe::BendL)
+        )
+    }
+
+    fn dirs(&self) -> Option<(Dir, Dir)> {
+        match self {
+            Pipe::Vert => Some((Dir::Up, Dir::Down)),
+            Pipe::Horiz => Some((Dir::Left, Dir::Right)),
+            Pipe::BendL => Some((Dir::Up, Dir::Right)),
+            Pipe::BendJ => Some((Dir::Up, Dir::Left)),
+            Pipe::Bend7 => Some((Dir::Left, Dir::Down)),
+            Pipe::BendF => Some((Dir::Down, Dir::Right)),
+            _ => None,
         }
     }
 
+    fn from_dirs(a: Dir, b: Dir) -> Option<Pipe> {
+        PIPES.into_iter().find(|p| {
+            let dirs = p.dirs().unwrap();
+            dirs == (a, b) || dirs == (b, a)
+        })
+    }
+
+    fn connects(&self, dir: Dir) -> bool {
+        self.dirs().is_some_and(|(a, b)| a == dir || b == dir)
+    }
+
     fn go(&self, from: Dir) -> Dir {
-        let dirs = match self {
-            Pipe::Vert => (Dir::Up, Dir::Down),
-            Pipe::Horiz => (Dir::Left, Dir::Right),
-            Pipe::BendL => (Dir::Up, Dir::Right),
-            Pipe::BendJ => (Dir::Up, Dir::Left),
-            Pipe::Bend7 => (Dir::Left, Dir::Down),
-            Pipe::BendF => (Dir::Down, Dir::Right),
-            _ => panic!("invalid travel"),
-        };
+        let dirs = self.dirs().expect("invalid travel");
         if dirs.0 == from.opposite() {
             dirs.1
         } else {
@@ -82,52 +112,20 @@ fn parse_pipe(c: char) -> Pipe {
     }
 }
 
-fn parse(input: &String) -> Vec<Vec<Pipe>> {
+fn parse(input: &str) -> Vec<Vec<Pipe>> {
     input
         .lines()
         .map(|x| x.chars().map(parse_pipe).collect())
         .collect()
 }
 
-fn find_start(map: &Vec<Vec<Pipe>>) -> (usize, usize) {
-    for y in 0..map.len() {
-        for x in 0..map[0].len() {
-            if map[y][x] == Pipe::Start {
-                return (x, y);
-            }
+fn find_start(map: &[Vec<Pipe>]) -> Result<(usize, usize), StartError> {
+    for (y, row) in map.iter().enumerate() {
+        if let Some(x) = row.iter().position(|p| *p == Pipe::Start) {
+            return Ok((x, y));
         }
     }
-    panic!("no start");
-}
-
-fn find_start_dir(map: &Vec<Vec<Pipe>>, x: usize, y: usize) -> Dir {
-    if y < map.len() - 1
-        && (map[y + 1][x] == Pipe::Vert
-            || map[y + 1][x] == Pipe::BendL
-            || map[y + 1][x] == Pipe::BendJ)
-    {
-        Dir::Down
-    } else if y > 0
-        && (map[y - 1][x] == Pipe::Vert
-            || map[y - 1][x] == Pipe::Bend7
-            || map[y - 1][x] == Pipe::BendF)
-    {
-        Dir::Up
-    } else if x > 0
-        && (map[y][x - 1] == Pipe::Horiz
-            || map[y][x - 1] == Pipe::BendL
-            || map[y][x - 1] == Pipe::BendF)
-    {
-        Dir::Left
-    } else if x < map[0].len() - 1
-        && (map[y][x + 1] == Pipe::Horiz
-            || map[y][x + 1] == Pipe::BendJ
-            || map[y][x + 1] == Pipe::Bend7)
-    {
-        Dir::Right
-    } else {
-        panic!("can't get started");
-    }
+    Err(StartError::Missing)
 }
 
 fn travel(x: usize, y: usize, dir: Dir) -> (usize, usize) {
@@ -139,59 +137,134 @@ fn travel(x: usize, y: usize, dir: Dir) -> (usize, usize) {
     }
 }
 
-fn run(input: &String) -> (Vec<Vec<Pipe>>, HashSet<(usize, usize)>, i64) {
-    let map = parse(input);
-    let (mut x, mut y) = find_start(&map);
-    let mut dir = find_start_dir(&map, x, y);
-    let mut points = HashSet::new();
+// like travel, but stays on the map
+fn step(
+    map: &[Vec<Pipe>], x: usize, y: usize, dir: Dir,
+) -> Option<(usize, usize)> {
+    let (x, y) = match dir {
+        Dir::Up => (x, y.checked_sub(1)?),
+        Dir::Down => (x, y + 1),
+        Dir::Left => (x.checked_sub(1)?, y),
+        Dir::Right => (x + 1, y),
+    };
+    (y < map.len() && x < map[y].len()).then_some((x, y))
+}
+
+// leave the start heading `dir` and follow the pipes; if they lead back to
+// the start, return the direction in which we arrive
+fn walk_back(map: &[Vec<Pipe>], x: usize, y: usize, dir: Dir) -> Option<Dir> {
+    let (mut x, mut y, mut dir) = (x, y, dir);
+    loop {
+        (x, y) = step(map, x, y, dir)?;
+        let p = map[y][x];
+        if p == Pipe::Start {
+            return Some(dir);
+        } else if !p.connects(dir.opposite()) {
+            return None;
+        }
+        dir = p.go(dir);
+    }
+}
+
+fn start_pipe(
+    map: &[Vec<Pipe>], x: usize, y: usize,
+) -> Result<Pipe, StartError> {
+    let mut found = Vec::new();
+    for dir in DIRS {
+        let Some(back) = walk_back(map, x, y, dir) else {
+            continue;
+        };
+        if let Some(p) = Pipe::from_dirs(dir, back.opposite()) {
+            if !found.contains(&p) {
+                found.push(p);
+            }
+        }
+    }
+    match found.len() {
+        0 => Err(StartError::DeadEnd),
+        1 => Ok(found[0]),
+        _ => {
+            found.sort();
+            Err(StartError::Ambiguous(found))
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Method {
+    RayCast,
+    Shoelace,
+}
+
+// the map has the start replaced by its real pipe; vertices are the loop's
+// corners in travel order
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Loop {
+    map: Vec<Vec<Pipe>>,
+    points: HashSet<(usize, usize)>,
+    vertices: Vec<(usize, usize)>,
+    len: i64,
+}
 
-    points.insert((x, y));
-    (x, y) = travel(x, y, dir);
-    let mut steps = 1;
+fn run(input: &str) -> Result<Loop, StartError> {
+    let mut map = parse(input);
+    let (mut x, mut y) = find_start(&map)?;
+    let start = start_pipe(&map, x, y)?;
+    map[y][x] = start;
+    // as if we had just come in through one of the start's connectors
+    let mut dir = start.dirs().unwrap().0.opposite();
+    let mut points = HashSet::new();
+    let mut vertices = Vec::new();
 
-    while map[y][x] != Pipe::Start {
+    while !points.contains(&(x, y)) {
         points.insert((x, y));
+        if map[y][x].is_corner() {
+            vertices.push((x, y));
+        }
         dir = map[y][x].go(dir);
         (x, y) = travel(x, y, dir);
-        steps += 1;
     }
 
-    (map, points, steps / 2)
+    Ok(Loop {
+        map,
+        len: points.len() as i64,
+        points,
+        vertices,
+    })
 }
 
-fn process1(input: &String) -> i64 {
-    run(input).2
+fn process1(input: &str) -> Result<i64, StartError> {
+    Ok(run(input)?.len / 2)
 }
 
-fn process2(input: &String, replace: Pipe) -> i64 {
-    let (mut map, points, _) = run(input);
-    let (x, y) = find_start(&map);
-    map[y][x] = replace;
-
-    for y in 0..map.len() {
-        for x in 0..map[0].len() {
+fn ray_cast(lp: Loop) -> i64 {
+    let Loop {
+        mut map, points, ..
+    } = lp;
+    for (y, row) in map.iter_mut().enumerate() {
+        for (x, p) in row.iter_mut().enumerate() {
             if !points.contains(&(x, y)) {
-                map[y][x] = Pipe::Empty;
+                *p = Pipe::Empty;
             }
         }
     }
 
     let mut count = 0;
-    for y in 0..map.len() {
-        for x in 0..map[0].len() {
-            if map[y][x] != Pipe::Empty {
+    for row in &map {
+        for (x, p) in row.iter().enumerate() {
+            if *p != Pipe::Empty {
                 continue;
             }
             let mut cross = 0;
             let mut edge = false;
             let mut corner = Pipe::Empty;
-            for x in x..map[0].len() {
-                if map[y][x] == Pipe::Vert {
+            for p in &row[x..] {
+                if *p == Pipe::Vert {
                     cross += 1;
-                } else if map[y][x].is_corner() {
+                } else if p.is_corner() {
                     if !edge {
-                        corner = map[y][x];
-                    } else if Pipe::is_run_diag(map[y][x], corner) {
+                        corner = *p;
+                    } else if Pipe::is_run_diag(*p, corner) {
                         cross += 1;
                     }
                     edge = !edge;
@@ -206,46 +279,392 @@ fn process2(input: &String, replace: Pipe) -> i64 {
     count
 }
 
+// twice the signed area inside the loop's vertices, then pick's theorem
+// a = i + b/2 - 1 gives the interior tiles i
+fn shoelace(lp: Loop) -> i64 {
+    let v = &lp.vertices;
+    let area2 = (0..v.len())
+        .map(|i| {
+            let (x0, y0) = v[i];
+            let (x1, y1) = v[(i + 1) % v.len()];
+            x0 as i64 * y1 as i64 - x1 as i64 * y0 as i64
+        })
+        .sum::<i64>()
+        .abs();
+    (area2 - lp.len) / 2 + 1
+}
+
+fn enclosed(input: &str, method: Method) -> Result<i64, StartError> {
+    let lp = run(input)?;
+    Ok(match method {
+        Method::RayCast => ray_cast(lp),
+        Method::Shoelace => shoelace(lp),
+    })
+}
+
+fn process2(input: &str) -> Result<i64, StartError> {
+    enclosed(input, Method::Shoelace)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Component {
+    tiles: Vec<(usize, usize)>,
+    broken: Vec<((usize, usize), Dir)>,
+}
+
+impl Component {
+    fn is_loop(&self) -> bool {
+        self.broken.is_empty()
+    }
+}
+
+fn render_pipe(p: Pipe) -> char {
+    match p {
+        Pipe::Vert => '|',
+        Pipe::Horiz => '-',
+        Pipe::BendL => 'L',
+        Pipe::BendJ => 'J',
+        Pipe::Bend7 => '7',
+        Pipe::BendF => 'F',
+        Pipe::Empty => '.',
+        Pipe::Start => 'S',
+    }
+}
+
+fn render(map: &[Vec<Pipe>]) -> String {
+    map.iter()
+        .map(|row| {
+            row.iter().map(|p| render_pipe(*p)).collect::<String>() + "\n"
+        })
+        .collect()
+}
+
+// connectors of the tile; an unresolved start reaches toward anything that
+// reaches back
+fn exits(map: &[Vec<Pipe>], x: usize, y: usize) -> Vec<Dir> {
+    match map[y][x] {
+        Pipe::Empty => Vec::new(),
+        Pipe::Start => DIRS
+            .into_iter()
+            .filter(|d| {
+                step(map, x, y, *d)
+                    .is_some_and(|(x, y)| map[y][x].connects(d.opposite()))
+            })
+            .collect(),
+        p => {
+            let (a, b) = p.dirs().unwrap();
+            vec![a, b]
+        }
+    }
+}
+
+// pipes joined by connectors that point at each other, in reading order of
+// their first tile
+fn components(map: &[Vec<Pipe>]) -> Vec<Component> {
+    let mut seen = HashSet::new();
+    let mut comps = Vec::new();
+
+    for (y, row) in map.iter().enumerate() {
+        for x in 0..row.len() {
+            if map[y][x] == Pipe::Empty || seen.contains(&(x, y)) {
+                continue;
+            }
+            let mut comp = Component {
+                tiles: Vec::new(),
+                broken: Vec::new(),
+            };
+            let mut todo = vec![(x, y)];
+            seen.insert((x, y));
+            while let Some((x, y)) = todo.pop() {
+                comp.tiles.push((x, y));
+                for dir in exits(map, x, y) {
+                    let next = step(map, x, y, dir).filter(|&(nx, ny)| {
+                        exits(map, nx, ny).contains(&dir.opposite())
+                    });
+                    match next {
+                        None => comp.broken.push(((x, y), dir)),
+                        Some(n) => {
+                            if seen.insert(n) {
+                                todo.push(n);
+                            }
+                        }
+                    }
+                }
+            }
+            comp.tiles.sort_by_key(|(x, y)| (*y, *x));
+            comp.broken.sort_by_key(|((x, y), d)| (*y, *x, *d));
+            comps.push(comp);
+        }
+    }
+    comps
+}
+
+// empty every pipe that isn't part of a closed loop
+fn clean(map: &mut [Vec<Pipe>]) {
+    for c in components(map).iter().filter(|c| !c.is_loop()) {
+        for (x, y) in &c.tiles {
+            map[*y][*x] = Pipe::Empty;
+        }
+    }
+}
+
+fn report(map: &[Vec<Pipe>]) {
+    for c in components(map) {
+        let (x, y) = c.tiles[0];
+        let kind = if c.is_loop() { "loop" } else { "open" };
+        println!("{kind} at ({x}, {y}): {} tiles", c.tiles.len());
+        for ((x, y), dir) in c.broken {
+            println!("  ({x}, {y}) {dir:?} goes nowhere");
+        }
+    }
+}
+
+fn solve(input: &str, part2: fn(&str) -> Result<i64, StartError>) {
+    match process1(input) {
+        Ok(total) => println!("1: {total}"),
+        Err(e) => println!("1: {e:?}"),
+    }
+    match part2(input) {
+        Ok(total) => println!("2: {total}"),
+        Err(e) => println!("2: {e:?}"),
+    }
+}
+
 fn main() {
     const INPUT: &str = "input.txt";
     let input = read_to_string(INPUT).unwrap();
-    let total = process1(&input);
-    println!("1: {total}");
-    let total = process2(&input, Pipe::Vert); // FIXME hardcoded
-    println!("2: {total}");
+    match env::args().nth(1).as_deref() {
+        Some("raycast") => solve(&input, |i| enclosed(i, Method::RayCast)),
+        Some("network") => report(&parse(&input)),
+        Some("clean") => {
+            let mut map = parse(&input);
+            clean(&mut map);
+            print!("{}", render(&map));
+        }
+        _ => solve(&input, process2),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use metamorphic::{cases, each_grid, flip_cols, flip_rows, invariant};
+    use metamorphic::{swap_chars, text, transpose, Rng};
+    use std::cmp::Ordering;
 
     #[test]
     fn example1a() {
         let input = read_to_string("example-a.txt").unwrap();
-        assert_eq!(process1(&input), 4);
+        assert_eq!(process1(&input), Ok(4));
     }
 
     #[test]
     fn example1b() {
         let input = read_to_string("example-b.txt").unwrap();
-        assert_eq!(process1(&input), 8);
+        assert_eq!(process1(&input), Ok(8));
     }
 
     #[test]
     fn example2c() {
         let input = read_to_string("example-c.txt").unwrap();
-        assert_eq!(process2(&input, Pipe::BendF), 4); // FIXME hardcoded
+        assert_eq!(process2(&input), Ok(4));
     }
 
     #[test]
     fn example2d() {
         let input = read_to_string("example-d.txt").unwrap();
-        assert_eq!(process2(&input, Pipe::BendF), 8); // FIXME hardcoded
+        assert_eq!(process2(&input), Ok(8));
     }
 
     #[test]
     fn example2e() {
         let input = read_to_string("example-e.txt").unwrap();
-        assert_eq!(process2(&input, Pipe::Bend7), 10); // FIXME hardcoded
+        assert_eq!(process2(&input), Ok(10));
+    }
+
+    #[test]
+    fn test_methods() {
+        for file in [
+            "example-a.txt",
+            "example-b.txt",
+            "example-c.txt",
+            "example-d.txt",
+            "example-e.txt",
+        ] {
+            let input = read_to_string(file).unwrap();
+            assert_eq!(
+                enclosed(&input, Method::RayCast),
+                enclosed(&input, Method::Shoelace),
+                "{file}"
+            );
+        }
+        let input = read_to_string("example-a.txt").unwrap();
+        let lp = run(&input).unwrap();
+        assert_eq!(lp.vertices, [(1, 1), (3, 1), (3, 3), (1, 3)]);
+        assert_eq!(shoelace(lp), 1);
+    }
+
+    fn start_of(input: &str) -> Result<Pipe, StartError> {
+        let map = parse(input);
+        let (x, y) = find_start(&map)?;
+        start_pipe(&map, x, y)
+    }
+
+    #[test]
+    fn test_start_pipe() {
+        for (file, pipe) in [
+            ("example-a.txt", Pipe::BendF),
+            ("example-b.txt", Pipe::BendF),
+            ("example-c.txt", Pipe::BendF),
+            ("example-d.txt", Pipe::BendF),
+            ("example-e.txt", Pipe::Bend7),
+        ] {
+            let input = read_to_string(file).unwrap();
+            assert_eq!(start_of(&input), Ok(pipe), "{file}");
+        }
+        // only two of the three connected neighbors lead back around
+        assert_eq!(start_of("F7.\nLS-\n..."), Ok(Pipe::BendJ));
+    }
+
+    #[test]
+    fn test_start_errors() {
+        assert_eq!(start_of("..\n.."), Err(StartError::Missing));
+        assert_eq!(start_of("S-\n.."), Err(StartError::DeadEnd));
+        assert_eq!(start_of("-S-\n..."), Err(StartError::DeadEnd));
+        assert_eq!(
+            start_of(".F-7\n.|.|\nFS-J\n||..\nLJ.."),
+            Err(StartError::Ambiguous(vec![Pipe::BendL, Pipe::Bend7]))
+        );
+    }
+
+    #[test]
+    fn test_components() {
+        let input = read_to_string("example-b.txt").unwrap();
+        let comps = components(&parse(&input));
+        assert_eq!(comps.len(), 1);
+        assert!(comps[0].is_loop());
+        assert_eq!(comps[0].tiles.len(), 16);
+
+        let input = read_to_string("example-e.txt").unwrap();
+        let map = parse(&input);
+        let comps = components(&map);
+        let loops = comps.iter().filter(|c| c.is_loop()).collect::<Vec<_>>();
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].tiles.len() as i64, run(&input).unwrap().len);
+        let total = comps.iter().map(|c| c.tiles.len()).sum::<usize>();
+        let pipes = map.iter().flatten().filter(|p| **p != Pipe::Empty);
+        assert_eq!(total, pipes.count());
+
+        let comps = components(&parse("-7.\n.|F\nL.."));
+        assert_eq!(comps.len(), 3);
+        assert_eq!(comps[0].tiles, [(0, 0), (1, 0), (1, 1)]);
+        assert_eq!(comps[0].broken, [((0, 0), Dir::Left), ((1, 1), Dir::Down)]);
+        assert_eq!(
+            comps[1].broken,
+            [((2, 1), Dir::Down), ((2, 1), Dir::Right)]
+        );
+        assert_eq!(comps[2].broken, [((0, 2), Dir::Up), ((0, 2), Dir::Right)]);
+    }
+
+    #[test]
+    fn test_clean() {
+        let input = read_to_string("example-b.txt").unwrap();
+        let mut map = parse(&input);
+        clean(&mut map);
+        assert_eq!(render(&map), input);
+
+        let mut map = parse(".F7-\n.LJ|\n7...");
+        clean(&mut map);
+        assert_eq!(render(&map), ".F7.\n.LJ.\n....\n");
+    }
+
+    fn toward(a: (usize, usize), b: (usize, usize)) -> Dir {
+        match (b.0.cmp(&a.0), b.1.cmp(&a.1)) {
+            (_, Ordering::Less) => Dir::Up,
+            (_, Ordering::Greater) => Dir::Down,
+            (Ordering::Less, _) => Dir::Left,
+            _ => Dir::Right,
+        }
+    }
+
+    // a loop running right along a top edge and back along a bottom one,
+    // each rising and falling as it goes, with S somewhere on it and stray
+    // pipes anywhere not next to S
+    fn looped(rng: &mut Rng) -> String {
+        let (w, h) = (rng.range(2, 12), rng.range(1, 5));
+        // rows where the top edge steps from column x to x + 1, and where
+        // the bottom steps back
+        let top = (1..w).map(|_| rng.range(0, h)).collect::<Vec<_>>();
+        let bottom = (1..w).map(|_| rng.range(h, 2 * h)).collect::<Vec<_>>();
+
+        let mut corners = vec![(0, top[0])];
+        for x in 1..w - 1 {
+            corners.extend([(x, top[x - 1]), (x, top[x])]);
+        }
+        corners.extend([(w - 1, top[w - 2]), (w - 1, bottom[w - 2])]);
+        for x in (1..w - 1).rev() {
+            corners.extend([(x, bottom[x]), (x, bottom[x - 1])]);
+        }
+        let mut path = vec![(0, bottom[0])];
+        for to in corners {
+            while let Some(&at) = path.last().filter(|at| **at != to) {
+                let next = travel(at.0, at.1, toward(at, to));
+                path.push(next);
+            }
+        }
+
+        // a border of empty ground all round
+        let mut map = vec![vec!['.'; w + 2]; 2 * h + 2];
+        for (i, &at) in path.iter().enumerate() {
+            let prev = path[(i + path.len() - 1) % path.len()];
+            let next = path[(i + 1) % path.len()];
+            let p = Pipe::from_dirs(toward(at, prev), toward(at, next));
+            map[at.1 + 1][at.0 + 1] = render_pipe(p.unwrap());
+        }
+        let (sx, sy) = path[rng.range(0, path.len())];
+        let (sx, sy) = (sx + 1, sy + 1);
+        map[sy][sx] = 'S';
+        for (y, row) in map.iter_mut().enumerate() {
+            for (x, c) in row.iter_mut().enumerate() {
+                let near = sx.abs_diff(x) + sy.abs_diff(y) <= 1;
+                if *c == '.' && !near && rng.range(0, 4) == 0 {
+                    *c = b"|-LJ7F"[rng.range(0, 6)] as char;
+                }
+            }
+        }
+        text(&map)
+    }
+
+    // each transform with the pipe shapes it changes
+    fn mirror(s: &str) -> String {
+        swap_chars(&each_grid(s, flip_cols), &[('F', '7'), ('L', 'J')])
+    }
+
+    fn upend(s: &str) -> String {
+        swap_chars(&each_grid(s, flip_rows), &[('F', 'L'), ('7', 'J')])
+    }
+
+    fn turn(s: &str) -> String {
+        swap_chars(&each_grid(s, transpose), &[('-', '|'), ('L', '7')])
+    }
+
+    #[test]
+    fn test_mirrored() {
+        let examples = ["b", "c", "d", "e"].map(|f| format!("example-{f}.txt"));
+        let examples = examples.each_ref().map(|f| f.as_str());
+        let cases = cases(100, &examples, looped);
+        for c in &cases {
+            let lp = run(c).unwrap();
+            assert_eq!(shoelace(lp.clone()), ray_cast(lp), "{c}");
+        }
+
+        let solve = |s: &str| {
+            let raycast = enclosed(s, Method::RayCast);
+            (process1(s), process2(s), raycast)
+        };
+        invariant(&cases, mirror, solve);
+        invariant(&cases, upend, solve);
+        invariant(&cases, turn, solve);
+        invariant(&cases, |s| mirror(&turn(s)), solve);
     }
 }
@@ -1,5 +1,8 @@
-use std::cmp::{max, min};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::env;
 use std::fs::read_to_string;
+use std::io::ErrorKind;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 enum Space {
@@ -15,104 +18,300 @@ fn parse_space(c: char) -> Space {
     }
 }
 
-fn parse(input: &String) -> Vec<Vec<Space>> {
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Axis {
+    Row,
+    Col,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum WeightError {
+    Format { line: usize },
+    Range { line: usize },
+    Occupied { line: usize },
+    Io { kind: ErrorKind },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum QueryError {
+    Weight(WeightError),
+    Bucket,
+    Number(String),
+}
+
+impl From<WeightError> for QueryError {
+    fn from(e: WeightError) -> Self {
+        QueryError::Weight(e)
+    }
+}
+
+fn parse(input: &str) -> Vec<Vec<Space>> {
     input
         .lines()
         .map(|x| x.chars().map(parse_space).collect())
         .collect()
 }
 
-fn find_empty_rows(map: &Vec<Vec<Space>>) -> Vec<usize> {
-    let mut rows = Vec::new();
-    for y in 0..map.len() {
-        let mut total = 0;
-        for x in 0..map[0].len() {
-            if map[y][x] == Space::Galaxy {
-                total += 1;
+fn find_empty_rows(map: &[Vec<Space>]) -> Vec<usize> {
+    (0..map.len())
+        .filter(|y| map[*y].iter().all(|s| *s == Space::Empty))
+        .collect()
+}
+
+fn find_empty_cols(map: &[Vec<Space>]) -> Vec<usize> {
+    (0..map[0].len())
+        .filter(|x| map.iter().all(|row| row[*x] == Space::Empty))
+        .collect()
+}
+
+fn find_gals(map: &[Vec<Space>]) -> Vec<(usize, usize)> {
+    let mut gals = Vec::new();
+    for (y, row) in map.iter().enumerate() {
+        for (x, s) in row.iter().enumerate() {
+            if *s == Space::Galaxy {
+                gals.push((x, y));
             }
         }
-        if total == 0 {
-            rows.push(y);
-        }
     }
-    rows
+    gals
 }
 
-fn find_empty_cols(map: &Vec<Vec<Space>>) -> Vec<usize> {
-    let mut cols = Vec::new();
-    for x in 0..map[0].len() {
-        let mut total = 0;
-        for y in 0..map.len() {
-            if map[y][x] == Space::Galaxy {
-                total += 1;
-            }
+// how wide every row and column is once the universe expands; only the
+// empty ones may be other than 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Expansion {
+    rows: Vec<usize>,
+    cols: Vec<usize>,
+}
+
+impl Expansion {
+    fn with<F>(map: &[Vec<Space>], weight: F) -> Expansion
+    where
+        F: Fn(Axis, usize) -> usize,
+    {
+        let mut rows = vec![1; map.len()];
+        for y in find_empty_rows(map) {
+            rows[y] = weight(Axis::Row, y);
         }
-        if total == 0 {
-            cols.push(x);
+        let mut cols = vec![1; map[0].len()];
+        for x in find_empty_cols(map) {
+            cols[x] = weight(Axis::Col, x);
         }
+        Expansion { rows, cols }
     }
-    cols
-}
 
-fn path(
-    a: (usize, usize), b: (usize, usize), expand: usize, rows: &Vec<usize>,
-    cols: &Vec<usize>,
-) -> usize {
-    let (x0, x1) = (min(a.0, b.0), max(a.0, b.0));
-    let (y0, y1) = (min(a.1, b.1), max(a.1, b.1));
-    let mut dist = x1 - x0 + y1 - y0;
-    for r in rows {
-        if y0 < *r && *r < y1 {
-            dist += expand - 1;
-        }
+    fn uniform(map: &[Vec<Space>], factor: usize) -> Expansion {
+        Expansion::with(map, |_, _| factor)
     }
-    for c in cols {
-        if x0 < *c && *c < x1 {
-            dist += expand - 1;
+
+    // lines of "row N WEIGHT" or "col N WEIGHT" for single empty rows and
+    // columns, and "default WEIGHT" for the rest, which is 2 if not given
+    fn parse(map: &[Vec<Space>], weights: &str) -> Result<Self, WeightError> {
+        let mut default = 2;
+        let mut set = Vec::new();
+        for (i, text) in weights.lines().enumerate() {
+            let line = i + 1;
+            let num = |s: &str| s.parse().or(Err(WeightError::Format { line }));
+            match text.split_whitespace().collect::<Vec<_>>()[..] {
+                [] => {}
+                ["default", w] => default = num(w)?,
+                ["row", n, w] => set.push((Axis::Row, num(n)?, num(w)?, line)),
+                ["col", n, w] => set.push((Axis::Col, num(n)?, num(w)?, line)),
+                _ => return Err(WeightError::Format { line }),
+            }
         }
+
+        let mut e = Expansion::uniform(map, default);
+        let empty = (find_empty_rows(map), find_empty_cols(map));
+        for (axis, n, w, line) in set {
+            let (widths, empty) = match axis {
+                Axis::Row => (&mut e.rows, &empty.0),
+                Axis::Col => (&mut e.cols, &empty.1),
+            };
+            if n >= widths.len() {
+                return Err(WeightError::Range { line });
+            } else if !empty.contains(&n) {
+                return Err(WeightError::Occupied { line });
+            }
+            widths[n] = w;
+        }
+        Ok(e)
+    }
+
+    // galaxies at their expanded coordinates, where each row and column
+    // starts after the full width of those before it
+    fn place(&self, gals: &[(usize, usize)]) -> Vec<(usize, usize)> {
+        let starts = |widths: &[usize]| {
+            widths
+                .iter()
+                .scan(0, |at, w| {
+                    *at += w;
+                    Some(*at - w)
+                })
+                .collect::<Vec<_>>()
+        };
+        let (ys, xs) = (starts(&self.rows), starts(&self.cols));
+        gals.iter().map(|(x, y)| (xs[*x], ys[*y])).collect()
     }
-    dist
 }
 
-fn find_gals(map: &Vec<Vec<Space>>) -> Vec<(usize, usize)> {
-    let mut gals = Vec::new();
-    for y in 0..map.len() {
-        for x in 0..map[0].len() {
-            if map[y][x] == Space::Galaxy {
-                gals.push((x, y));
+fn distance(a: (usize, usize), b: (usize, usize)) -> usize {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+// the sum over every pair, an axis at a time: in sorted order each
+// coordinate lies beyond all i of those before it
+fn total_distance(gals: &[(usize, usize)]) -> usize {
+    let axis = |mut c: Vec<usize>| {
+        c.sort();
+        let mut before = 0;
+        let mut total = 0;
+        for (i, c) in c.iter().enumerate() {
+            total += c * i - before;
+            before += c;
+        }
+        total
+    };
+    axis(gals.iter().map(|g| g.0).collect())
+        + axis(gals.iter().map(|g| g.1).collect())
+}
+
+// (galaxy, distance) of the nearest and the farthest other galaxy, the
+// lowest numbered on ties
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Extremes {
+    nearest: (usize, usize),
+    farthest: (usize, usize),
+}
+
+fn extremes(gals: &[(usize, usize)]) -> Vec<Option<Extremes>> {
+    (0..gals.len())
+        .map(|a| {
+            let others = (0..gals.len())
+                .filter(|b| *b != a)
+                .map(|b| (b, distance(gals[a], gals[b])));
+            let nearest = others.clone().min_by_key(|(b, d)| (*d, *b))?;
+            let farthest = others.max_by_key(|(b, d)| (*d, Reverse(*b)))?;
+            Some(Extremes { nearest, farthest })
+        })
+        .collect()
+}
+
+// the k closest (a, b, distance) with a < b, nearest first and then by
+// galaxy; a heap of the best k so far drops the farthest as it overflows
+fn closest_pairs(
+    gals: &[(usize, usize)], k: usize,
+) -> Vec<(usize, usize, usize)> {
+    let mut heap = BinaryHeap::new();
+    for a in 0..gals.len() {
+        for b in a + 1..gals.len() {
+            heap.push((distance(gals[a], gals[b]), a, b));
+            if heap.len() > k {
+                heap.pop();
             }
         }
     }
-    gals
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|(d, a, b)| (a, b, d))
+        .collect()
 }
 
-fn run(input: &String, expand: usize) -> usize {
-    let map = parse(input);
-    let rows = find_empty_rows(&map);
-    let cols = find_empty_cols(&map);
-    let gals = find_gals(&map);
-
-    let mut total = 0;
+// how many pairs lie in each run of `bucket` distances, by its start
+fn histogram(
+    gals: &[(usize, usize)], bucket: usize,
+) -> Result<BTreeMap<usize, usize>, QueryError> {
+    if bucket == 0 {
+        return Err(QueryError::Bucket);
+    }
+    let mut counts = BTreeMap::new();
     for a in 0..gals.len() {
         for b in a + 1..gals.len() {
-            total += path(gals[a], gals[b], expand, &rows, &cols);
+            let d = distance(gals[a], gals[b]);
+            *counts.entry(d / bucket * bucket).or_insert(0) += 1;
         }
     }
+    Ok(counts)
+}
 
-    total
+fn run(input: &str, expand: usize) -> usize {
+    let map = parse(input);
+    let e = Expansion::uniform(&map, expand);
+    total_distance(&e.place(&find_gals(&map)))
 }
 
-fn process1(input: &String) -> usize {
+fn process1(input: &str) -> usize {
     run(input, 2)
 }
 
-fn process2(input: &String) -> usize {
+fn process2(input: &str) -> usize {
     run(input, 1000000)
 }
 
+// a plain factor, or the name of a weights file
+fn expansion(map: &[Vec<Space>], spec: &str) -> Result<Expansion, WeightError> {
+    match spec.parse() {
+        Ok(factor) => Ok(Expansion::uniform(map, factor)),
+        Err(_) => match read_to_string(spec) {
+            Ok(weights) => Expansion::parse(map, &weights),
+            Err(e) => Err(WeightError::Io { kind: e.kind() }),
+        },
+    }
+}
+
+// galaxies are numbered from 1, as in the puzzle
+fn query(map: &[Vec<Space>], args: &[&str]) -> Result<(), QueryError> {
+    let gals = |spec| expansion(map, spec).map(|e| e.place(&find_gals(map)));
+    let num = |s: &str| s.parse().or(Err(QueryError::Number(s.to_string())));
+    match *args {
+        ["sum", spec] => println!("{}", total_distance(&gals(spec)?)),
+        ["extremes", spec] => {
+            for (i, e) in extremes(&gals(spec)?).iter().enumerate() {
+                let Some(Extremes { nearest, farthest }) = e else {
+                    println!("{}: alone", i + 1);
+                    continue;
+                };
+                println!(
+                    "{}: nearest {} at {}, farthest {} at {}",
+                    i + 1,
+                    nearest.0 + 1,
+                    nearest.1,
+                    farthest.0 + 1,
+                    farthest.1
+                );
+            }
+        }
+        ["closest", k, spec] => {
+            for (a, b, d) in closest_pairs(&gals(spec)?, num(k)?) {
+                println!("{} {}: {d}", a + 1, b + 1);
+            }
+        }
+        ["histogram", bucket, spec] => {
+            let bucket = num(bucket)?;
+            for (start, n) in histogram(&gals(spec)?, bucket)? {
+                println!("{start}..{}: {n}", start + bucket);
+            }
+        }
+        _ => println!(
+            "usage: day11 [sum | extremes | closest K | histogram BUCKET] \
+             FACTOR|WEIGHTS"
+        ),
+    }
+    Ok(())
+}
+
 fn main() {
     const INPUT: &str = "input.txt";
     let input = read_to_string(INPUT).unwrap();
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(|x| x.as_str()).collect::<Vec<_>>();
+    if !args.is_empty() {
+        if let Err(e) = query(&parse(&input), &args) {
+            println!("{e:?}");
+        }
+        return;
+    }
+
     let total = process1(&input);
     println!("1: {total}");
     let total = process2(&input);
@@ -122,6 +321,8 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use metamorphic::{cases, check, each_grid, flip_cols, insert_col, Rng};
+    use metamorphic::{insert_row, invariant, rotate_cw, text, transpose};
 
     const EXAMPLE: &str = "example.txt";
 
@@ -142,4 +343,141 @@ mod tests {
         let input = read_to_string(EXAMPLE).unwrap();
         assert_eq!(run(&input, 100), 8410);
     }
+
+    #[test]
+    fn test_weights() {
+        let input = read_to_string(EXAMPLE).unwrap();
+        let map = parse(&input);
+        let e = Expansion::uniform(&map, 10);
+        assert_eq!(e.rows, [1, 1, 1, 10, 1, 1, 1, 10, 1, 1]);
+        assert_eq!(e.cols, [1, 1, 10, 1, 1, 10, 1, 1, 10, 1]);
+        assert_eq!(Expansion::parse(&map, "default 10"), Ok(e));
+        assert_eq!(Expansion::parse(&map, ""), Ok(Expansion::uniform(&map, 2)));
+
+        let e =
+            Expansion::parse(&map, "default 10\n\nrow 3 2\ncol 8 0").unwrap();
+        assert_eq!(e.rows[3], 2);
+        assert_eq!(e.cols, [1, 1, 10, 1, 1, 10, 1, 1, 0, 1]);
+        let gals = e.place(&find_gals(&map));
+        assert_eq!(gals[0], (12, 0));
+        assert_eq!(gals[4], (1, 6));
+        assert_eq!(gals[5], (26, 7));
+
+        // weights from a function, say growing toward the bottom right
+        let f = Expansion::with(&map, |axis, i| match axis {
+            Axis::Row => i,
+            Axis::Col => 2 * i,
+        });
+        assert_eq!(f.rows[7], 7);
+        assert_eq!(f.cols[8], 16);
+        for gals in [gals, f.place(&find_gals(&map))] {
+            let pairs = (0..gals.len())
+                .flat_map(|a| (a + 1..gals.len()).map(move |b| (a, b)))
+                .map(|(a, b)| distance(gals[a], gals[b]));
+            assert_eq!(total_distance(&gals), pairs.sum());
+        }
+
+        for (weights, err) in [
+            ("row 0 5", WeightError::Occupied { line: 1 }),
+            ("\ncol 10 5", WeightError::Range { line: 2 }),
+            ("row three 5", WeightError::Format { line: 1 }),
+            ("default", WeightError::Format { line: 1 }),
+            ("diag 3 5", WeightError::Format { line: 1 }),
+        ] {
+            assert_eq!(Expansion::parse(&map, weights), Err(err), "{weights}");
+        }
+        assert_eq!(
+            expansion(&map, "no-such-weights.txt"),
+            Err(WeightError::Io {
+                kind: ErrorKind::NotFound
+            })
+        );
+    }
+
+    #[test]
+    fn test_queries() {
+        let input = read_to_string(EXAMPLE).unwrap();
+        let map = parse(&input);
+        let gals = Expansion::uniform(&map, 2).place(&find_gals(&map));
+        assert_eq!(distance(gals[4], gals[8]), 9);
+        assert_eq!(distance(gals[0], gals[6]), 15);
+        assert_eq!(distance(gals[2], gals[5]), 17);
+
+        let ex = extremes(&gals);
+        assert_eq!(
+            ex[0],
+            Some(Extremes {
+                nearest: (1, 6),
+                farthest: (5, 15)
+            })
+        );
+        // 3 and 5 are both 5 from 2, and 8 and 9 both 19
+        assert_eq!(ex[1].unwrap().nearest, (3, 5));
+        assert_eq!(ex[1].unwrap().farthest, (7, 19));
+        assert_eq!(extremes(&gals[..1]), [None]);
+
+        assert_eq!(closest_pairs(&gals, 2), [(1, 3, 5), (2, 4, 5)]);
+        assert_eq!(closest_pairs(&gals, 100).len(), 36);
+        assert_eq!(closest_pairs(&gals, 0), []);
+
+        let h = histogram(&gals, 5).unwrap();
+        assert_eq!(
+            h.into_iter().collect::<Vec<_>>(),
+            [(5, 18), (10, 11), (15, 7)]
+        );
+        let h = histogram(&gals, 1).unwrap();
+        assert_eq!(h.iter().map(|(d, n)| d * n).sum::<usize>(), 374);
+        assert_eq!(histogram(&gals, 0), Err(QueryError::Bucket));
+        let arg = QueryError::Number("x".to_string());
+        assert_eq!(query(&map, &["closest", "x", "2"]), Err(arg));
+    }
+
+    fn galaxies(rng: &mut Rng) -> String {
+        let (w, h) = (rng.range(1, 12), rng.range(1, 12));
+        text(&rng.grid(w, h, &[('.', 6), ('#', 1)]))
+    }
+
+    #[test]
+    fn test_symmetric() {
+        let cases = cases(100, &[EXAMPLE], galaxies);
+        for expand in [2, 10, 1000000] {
+            let solve = |s: &str| run(s, expand);
+            invariant(&cases, |s| each_grid(s, transpose), solve);
+            invariant(&cases, |s| each_grid(s, rotate_cw), solve);
+            invariant(&cases, |s| each_grid(s, flip_cols), solve);
+        }
+    }
+
+    // pairs of galaxies on either side of `at`, along x or y
+    fn separated(
+        input: &str, at: usize, coord: fn(&(usize, usize)) -> usize,
+    ) -> usize {
+        let gals = find_gals(&parse(input));
+        let before = gals.iter().filter(|g| coord(g) < at).count();
+        before * (gals.len() - before)
+    }
+
+    // an empty row or column through the middle stretches the path of
+    // every pair it separates by one expanded row, and leaves the rest alone
+    #[test]
+    fn test_inserted() {
+        let cases = cases(100, &[EXAMPLE], galaxies);
+        let mid_row = |s: &str| s.lines().count() / 2;
+        let mid_col = |s: &str| s.lines().next().map_or(0, |l| l.len()) / 2;
+        for expand in [2, 10, 1000000] {
+            let solve = |s: &str| run(s, expand);
+            let holds =
+                |a: &(usize, usize), b: &usize| *b == a.0 + expand * a.1;
+
+            let insert =
+                |s: &str| each_grid(s, |g| insert_row(g, mid_row(s), '.'));
+            let rows = |s: &str| (solve(s), separated(s, mid_row(s), |g| g.1));
+            check(&cases, insert, rows, solve, holds);
+
+            let insert =
+                |s: &str| each_grid(s, |g| insert_col(g, mid_col(s), '.'));
+            let cols = |s: &str| (solve(s), separated(s, mid_col(s), |g| g.0));
+            check(&cases, insert, cols, solve, holds);
+        }
+    }
 }
@@ -1,3 +1,6 @@
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use records::records;
 use std::fs::read_to_string;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -14,24 +17,17 @@ fn parse_space(c: char) -> Space {
     }
 }
 
-fn parse(input: &String) -> Vec<Vec<Vec<Space>>> {
-    let mut maps = Vec::new();
-    let mut map = Vec::new();
-
-    for line in input.lines() {
-        if line.is_empty() {
-            maps.push(map);
-            map = Vec::new();
-        } else {
-            map.push(line.chars().map(parse_space).collect());
-        }
-    }
-
-    maps.push(map);
-    maps
+fn parse(input: &str) -> Vec<Vec<Vec<Space>>> {
+    records(input.lines())
+        .map(|map| {
+            map.iter()
+                .map(|line| line.chars().map(parse_space).collect())
+                .collect()
+        })
+        .collect()
 }
 
-fn find_mirror_row(map: &Vec<Vec<Space>>, not: Option<usize>) -> Option<usize> {
+fn find_mirror_row(map: &[Vec<Space>], not: Option<usize>) -> Option<usize> {
     'middle: for m in 0..map.len() - 1 {
         // after
         let top = m + 1;
@@ -51,18 +47,15 @@ fn find_mirror_row(map: &Vec<Vec<Space>>, not: Option<usize>) -> Option<usize> {
     None
 }
 
-fn find_mirror_rows(maps: &Vec<Vec<Vec<Space>>>) -> Vec<usize> {
-    let mut rows = Vec::new();
-    for map in maps {
-        match find_mirror_row(map, None) {
-            None => (),
-            Some(m) => rows.push(m),
-        };
-    }
-    rows
+fn find_mirror_rows(maps: &[Vec<Vec<Space>>]) -> Vec<usize> {
+    #[cfg(feature = "parallel")]
+    let maps = maps.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let maps = maps.iter();
+    maps.filter_map(|map| find_mirror_row(map, None)).collect()
 }
 
-fn find_mirror_col(map: &Vec<Vec<Space>>, not: Option<usize>) -> Option<usize> {
+fn find_mirror_col(map: &[Vec<Space>], not: Option<usize>) -> Option<usize> {
     'middle: for m in 0..map[0].len() - 1 {
         // after
         let left = m + 1;
@@ -71,10 +64,8 @@ fn find_mirror_col(map: &Vec<Vec<Space>>, not: Option<usize>) -> Option<usize> {
         for i in 0..check {
             let x0 = m - i;
             let x1 = m + 1 + i;
-            for y in 0..map.len() {
-                if map[y][x0] != map[y][x1] {
-                    continue 'middle;
-                }
+            if map.iter().any(|row| row[x0] != row[x1]) {
+                continue 'middle;
             }
         }
         if not.is_none() || not.unwrap() != m {
@@ -84,73 +75,71 @@ fn find_mirror_col(map: &Vec<Vec<Space>>, not: Option<usize>) -> Option<usize> {
     None
 }
 
-fn find_mirror_cols(maps: &Vec<Vec<Vec<Space>>>) -> Vec<usize> {
-    let mut cols = Vec::new();
-    for map in maps {
-        match find_mirror_col(map, None) {
-            None => (),
-            Some(m) => cols.push(m),
-        };
-    }
-    cols
+fn find_mirror_cols(maps: &[Vec<Vec<Space>>]) -> Vec<usize> {
+    #[cfg(feature = "parallel")]
+    let maps = maps.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let maps = maps.iter();
+    maps.filter_map(|map| find_mirror_col(map, None)).collect()
 }
 
-fn process1(input: &String) -> usize {
+fn summarize(rows: &[usize], cols: &[usize]) -> usize {
+    cols.iter().map(|x| x + 1).sum::<usize>()
+        + rows.iter().map(|x| 100 * (x + 1)).sum::<usize>()
+}
+
+fn process1(input: &str) -> usize {
     let maps = parse(input);
     let rows = find_mirror_rows(&maps);
     let cols = find_mirror_cols(&maps);
-    cols.iter().map(|x| x + 1).sum::<usize>()
-        + rows.iter().map(|x| 100 * (x + 1)).sum::<usize>()
+    summarize(&rows, &cols)
 }
 
-fn swap(map: &mut Vec<Vec<Space>>, x: usize, y: usize) {
+fn swap(map: &mut [Vec<Space>], x: usize, y: usize) {
     map[y][x] = match map[y][x] {
         Space::Ash => Space::Rock,
         Space::Rock => Space::Ash,
     };
 }
 
-fn maybe_push(new: Option<usize>, res: &mut Vec<usize>) -> bool {
-    match new {
-        None => false,
-        Some(x) => {
-            res.push(x);
-            true
-        },
-    }
-}
+// the new (row, col) reflection once the smudge is cleaned
+fn find_smudge(map: &[Vec<Space>]) -> (Option<usize>, Option<usize>) {
+    let mut map = map.to_vec();
+    let old_row = find_mirror_row(&map, None);
+    let old_col = find_mirror_col(&map, None);
+
+    for y in 0..map.len() {
+        for x in 0..map[0].len() {
+            swap(&mut map, x, y);
+            let new = find_mirror_row(&map, old_row);
+            swap(&mut map, x, y);
+            if new.is_some() {
+                return (new, None);
+            }
 
-fn process2(input: &String) -> usize {
-    let mut maps = parse(input);
-    let mut rows = Vec::new();
-    let mut cols = Vec::new();
-
-    'outer: for i in 0..maps.len() {
-        assert_eq!(rows.len() + cols.len(), i);
-        for y in 0..maps[i].len() {
-            for x in 0..maps[i][0].len() {
-                let mut map = &mut maps[i];
-                let old = find_mirror_row(&map, None);
-                swap(&mut map, x, y);
-                let new = find_mirror_row(&map, old);
-                swap(&mut map, x, y);
-                if maybe_push(new, &mut rows) {
-                    continue 'outer;
-                }
-
-                let old = find_mirror_col(&map, None);
-                swap(&mut map, x, y);
-                let new = find_mirror_col(&map, old);
-                swap(&mut map, x, y);
-                if maybe_push(new, &mut cols) {
-                    continue 'outer;
-                }
+            swap(&mut map, x, y);
+            let new = find_mirror_col(&map, old_col);
+            swap(&mut map, x, y);
+            if new.is_some() {
+                return (None, new);
             }
         }
     }
+    (None, None)
+}
+
+fn process2(input: &str) -> usize {
+    let maps = parse(input);
+    #[cfg(feature = "parallel")]
+    let fixed = maps.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let fixed = maps.iter();
+    let fixed = fixed.map(|m| find_smudge(m)).collect::<Vec<_>>();
+
+    let rows = fixed.iter().filter_map(|x| x.0).collect::<Vec<_>>();
+    let cols = fixed.iter().filter_map(|x| x.1).collect::<Vec<_>>();
     assert_eq!(rows.len() + cols.len(), maps.len());
-    cols.iter().map(|x| x + 1).sum::<usize>()
-        + rows.iter().map(|x| 100 * (x + 1)).sum::<usize>()
+    summarize(&rows, &cols)
 }
 
 fn main() {
@@ -165,6 +154,7 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use metamorphic::{cases, check, each_grid, text, transpose, Rng};
 
     const EXAMPLE: &str = "example.txt";
 
@@ -200,4 +190,58 @@ mod tests {
         assert_eq!(process1(&input), 1);
         assert_eq!(process2(&input), 9);
     }
+
+    #[test]
+    fn test_endings() {
+        let input = read_to_string(EXAMPLE).unwrap();
+        let crlf = input.replace('\n', "\r\n");
+        let spaced = format!("\n{}\n\n", input.replace("\n\n", "\n\n\n"));
+        for input in [input.trim_end(), &crlf, &spaced] {
+            assert_eq!(parse(input).len(), 2);
+            assert_eq!(process1(input), 405);
+        }
+    }
+
+    // a pattern reflected after a random row, half the time turned so
+    // that it reflects after a column instead
+    fn mirrored(rng: &mut Rng) -> String {
+        let (w, h) = (rng.range(2, 10), rng.range(2, 10));
+        let mut g = rng.grid(w, h, &[('.', 1), ('#', 1)]);
+        let m = rng.range(1, h);
+        for y in m..h.min(2 * m) {
+            g[y] = g[2 * m - 1 - y].clone();
+        }
+        if rng.range(0, 2) == 1 {
+            g = transpose(&g);
+        }
+        text(&g)
+    }
+
+    // each pattern's reflections, by row and by column
+    fn mirrors(input: &str) -> (Vec<usize>, Vec<usize>) {
+        let maps = parse(input);
+        (find_mirror_rows(&maps), find_mirror_cols(&maps))
+    }
+
+    fn smudges(input: &str) -> Vec<(Option<usize>, Option<usize>)> {
+        parse(input).iter().map(|m| find_smudge(m)).collect()
+    }
+
+    #[test]
+    fn test_transpose() {
+        let examples = [EXAMPLE, "ex4.txt", "ex5.txt"];
+        let cases = cases(100, &examples, |rng| {
+            let n = rng.range(1, 4);
+            (0..n).map(|_| mirrored(rng)).collect::<Vec<_>>().join("\n")
+        });
+        let turn = |s: &str| each_grid(s, transpose);
+        check(&cases, turn, mirrors, mirrors, |a, b| {
+            a.0 == b.1 && a.1 == b.0
+        });
+
+        // the puzzle promises one smudge per pattern, so only the examples
+        check(&cases[100..], turn, smudges, smudges, |a, b| {
+            a.iter().zip(b).all(|(a, b)| a.0 == b.1 && a.1 == b.0)
+        });
+    }
 }
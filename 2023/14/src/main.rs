@@ -1,7 +1,9 @@
-use std::fs::read_to_string;
-use std::collections::HashMap;
 use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::env;
+use std::fs::read_to_string;
 use std::hash::{Hash, Hasher};
+use std::str::FromStr;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 enum Space {
@@ -19,7 +21,7 @@ fn parse_space(c: char) -> Space {
     }
 }
 
-fn parse(input: &String) -> Vec<Vec<Space>> {
+fn parse(input: &str) -> Vec<Vec<Space>> {
     let mut map = Vec::new();
 
     for line in input.lines() {
@@ -29,125 +31,203 @@ fn parse(input: &String) -> Vec<Vec<Space>> {
     map
 }
 
-fn roll_north(map: &mut Vec<Vec<Space>>) {
-    for y in 1..map.len() {
-        for x in 0..map[0].len() {
-            let mut y = y;
-            while y >= 1
-                && map[y][x] == Space::Round
-                && map[y - 1][x] == Space::Empty
-            {
-                map[y][x] = Space::Empty;
-                map[y - 1][x] = Space::Round;
-                y -= 1;
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Dir {
+    North,
+    West,
+    South,
+    East,
+}
+
+const DIRS: [Dir; 4] = [Dir::North, Dir::West, Dir::South, Dir::East];
+
+impl Dir {
+    // the lines rocks roll along, each listed from the edge they roll to
+    fn lanes(self, w: usize, h: usize) -> Vec<Vec<(usize, usize)>> {
+        let mut lanes = match self {
+            Dir::North | Dir::South => (0..w)
+                .map(|x| (0..h).map(|y| (x, y)).collect())
+                .collect::<Vec<Vec<_>>>(),
+            Dir::West | Dir::East => {
+                (0..h).map(|y| (0..w).map(|x| (x, y)).collect()).collect()
+            }
+        };
+        if matches!(self, Dir::South | Dir::East) {
+            for lane in &mut lanes {
+                lane.reverse();
             }
         }
+        lanes
     }
 }
 
-fn roll_south(map: &mut Vec<Vec<Space>>) {
-    for y in (0..map.len() - 1).rev() {
-        for x in 0..map[0].len() {
-            let mut y = y;
-            while y <= map.len() - 2
-                && map[y][x] == Space::Round
-                && map[y + 1][x] == Space::Empty
-            {
-                map[y][x] = Space::Empty;
-                map[y + 1][x] = Space::Round;
-                y += 1;
+// roll every round rock toward `dir` until it meets the edge or another
+// rock
+fn tilt(map: &mut [Vec<Space>], dir: Dir) {
+    for lane in dir.lanes(map[0].len(), map.len()) {
+        let mut free = 0;
+        for (i, &(x, y)) in lane.iter().enumerate() {
+            match map[y][x] {
+                Space::Square => free = i + 1,
+                Space::Round => {
+                    map[y][x] = Space::Empty;
+                    let (x, y) = lane[free];
+                    map[y][x] = Space::Round;
+                    free += 1;
+                }
+                Space::Empty => {}
             }
         }
     }
 }
 
-fn roll_west(map: &mut Vec<Vec<Space>>) {
-    for y in 0..map.len() {
-        for x in 1..map[0].len() {
-            let mut x = x;
-            while x >= 1
-                && map[y][x] == Space::Round
-                && map[y][x - 1] == Space::Empty
-            {
-                map[y][x] = Space::Empty;
-                map[y][x - 1] = Space::Round;
-                x -= 1;
+// each round rock weighs as many rows or columns as there are from it to
+// the far side of the `edge`
+fn load(map: &[Vec<Space>], edge: Dir) -> usize {
+    let (w, h) = (map[0].len(), map.len());
+    let mut total = 0;
+    for (y, row) in map.iter().enumerate() {
+        for (x, s) in row.iter().enumerate() {
+            if *s == Space::Round {
+                total += match edge {
+                    Dir::North => h - y,
+                    Dir::South => y + 1,
+                    Dir::West => w - x,
+                    Dir::East => x + 1,
+                };
             }
         }
     }
+    total
 }
 
-fn roll_east(map: &mut Vec<Vec<Space>>) {
-    for y in 0..map.len() {
-        for x in (0..map[0].len() - 1).rev() {
-            let mut x = x;
-            while x <= map[0].len() - 2
-                && map[y][x] == Space::Round
-                && map[y][x + 1] == Space::Empty
-            {
-                map[y][x] = Space::Empty;
-                map[y][x + 1] = Space::Round;
-                x += 1;
-            }
+// tilts to make in turn, the lot repeated `times`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+    tilts: Vec<Dir>,
+    times: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ScriptError {
+    Empty,
+    Token(String),
+    Count(String),
+}
+
+// steps separated by ';', each of N, W, S or E tilts and an optional
+// count like x3 at the end: "N W S E x1000000000; N"
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Script {
+    steps: Vec<Step>,
+}
+
+impl FromStr for Step {
+    type Err = ScriptError;
+
+    fn from_str(s: &str) -> Result<Step, ScriptError> {
+        let mut tokens = s.split_whitespace().collect::<Vec<_>>();
+        let mut times = 1;
+        if let Some(n) = tokens.last().and_then(|t| t.strip_prefix('x')) {
+            let count =
+                || ScriptError::Count(tokens.last().unwrap().to_string());
+            times = n.parse().map_err(|_| count())?;
+            tokens.pop();
+        }
+        let tilts = tokens
+            .iter()
+            .map(|t| match *t {
+                "N" => Ok(Dir::North),
+                "W" => Ok(Dir::West),
+                "S" => Ok(Dir::South),
+                "E" => Ok(Dir::East),
+                _ => Err(ScriptError::Token(t.to_string())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if tilts.is_empty() {
+            return Err(ScriptError::Empty);
         }
+        Ok(Step { tilts, times })
     }
 }
 
-fn spin(map: &mut Vec<Vec<Space>>) {
-    roll_north(map);
-    roll_west(map);
-    roll_south(map);
-    roll_east(map);
+impl FromStr for Script {
+    type Err = ScriptError;
+
+    fn from_str(s: &str) -> Result<Script, ScriptError> {
+        let steps =
+            s.split(';').map(|s| s.parse()).collect::<Result<_, _>>()?;
+        Ok(Script { steps })
+    }
 }
 
-fn load(map: &Vec<Vec<Space>>) -> usize {
-    let mut total = 0;
-    for y in 0..map.len() {
-        for x in 0..map[0].len() {
-            if map[y][x] == Space::Round {
-                total += map.len() - y;
+fn key(map: &[Vec<Space>]) -> u64 {
+    let mut h = DefaultHasher::new();
+    map.hash(&mut h);
+    h.finish()
+}
+
+// once a round of tilts leaves the rocks as an earlier one did, the rounds
+// repeat, so skip as many whole cycles as still fit
+fn perform(map: &mut [Vec<Space>], step: &Step) {
+    let mut seen = HashMap::new();
+    let mut skipped = false;
+    let mut i = 0;
+    while i < step.times {
+        if !skipped {
+            if let Some(j) = seen.insert(key(map), i) {
+                let len = i - j;
+                i += (step.times - i) / len * len;
+                skipped = true;
+                continue;
             }
         }
+        for dir in &step.tilts {
+            tilt(map, *dir);
+        }
+        i += 1;
+    }
+}
+
+fn run(map: &mut [Vec<Space>], script: &Script) {
+    for step in &script.steps {
+        perform(map, step);
     }
-    total
 }
 
-fn process1(input: &String) -> usize {
+fn process1(input: &str) -> usize {
     let mut map = parse(input);
-    roll_north(&mut map);
-    load(&map)
+    tilt(&mut map, Dir::North);
+    load(&map, Dir::North)
 }
 
-fn process2(input: &String) -> usize {
+fn process2(input: &str) -> usize {
     const SPINS: usize = 1000000000;
     let mut map = parse(input);
-    let mut seen = HashMap::new();
-    let (mut a, mut b) = (None, None);
-    for i in 0..SPINS {
-        let mut h = DefaultHasher::new();
-        map.hash(&mut h);
-        let h = h.finish();
-        if !seen.contains_key(&h) {
-            seen.insert(h, i);
-        } else {
-            (a, b) = (seen.get(&h), Some(i));
-            break;
-        }
-        spin(&mut map);
-    }
-
-    let (a, b) = (a.unwrap(), b.unwrap());
-    let len = b - a;
-    let restart = (SPINS - a) / len * len + a;
-    for _ in restart..SPINS {
-        spin(&mut map);
-    }
-    load(&map)
+    let step = Step {
+        tilts: DIRS.to_vec(),
+        times: SPINS,
+    };
+    perform(&mut map, &step);
+    load(&map, Dir::North)
 }
 
 fn main() {
     const INPUT: &str = "input.txt";
     let input = read_to_string(INPUT).unwrap();
+    if let Some(script) = env::args().nth(1) {
+        let script = match script.parse() {
+            Ok(script) => script,
+            Err(e) => return println!("{e:?}"),
+        };
+        let mut map = parse(&input);
+        run(&mut map, &script);
+        for edge in DIRS {
+            println!("{edge:?}: {}", load(&map, edge));
+        }
+        return;
+    }
+
     let total = process1(&input);
     println!("1: {total}");
     let total = process2(&input);
@@ -157,6 +237,8 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use metamorphic::{cases, check, each_grid, flip_cols, flip_rows, Rng};
+    use metamorphic::{rotate_ccw, rotate_cw, text, Grid};
 
     const EXAMPLE: &str = "example.txt";
 
@@ -164,7 +246,7 @@ mod tests {
     fn example_north() {
         let input = read_to_string(EXAMPLE).unwrap();
         let mut map = parse(&input);
-        roll_north(&mut map);
+        tilt(&mut map, Dir::North);
         let input = read_to_string("example-north.txt").unwrap();
         let expected = parse(&input);
         assert_eq!(map, expected);
@@ -174,7 +256,7 @@ mod tests {
     fn example_south() {
         let input = read_to_string(EXAMPLE).unwrap();
         let mut map = parse(&input);
-        roll_south(&mut map);
+        tilt(&mut map, Dir::South);
         let input = read_to_string("example-south.txt").unwrap();
         let expected = parse(&input);
         assert_eq!(map, expected);
@@ -184,7 +266,7 @@ mod tests {
     fn example_west() {
         let input = read_to_string(EXAMPLE).unwrap();
         let mut map = parse(&input);
-        roll_west(&mut map);
+        tilt(&mut map, Dir::West);
         let input = read_to_string("example-west.txt").unwrap();
         let expected = parse(&input);
         assert_eq!(map, expected);
@@ -194,7 +276,7 @@ mod tests {
     fn example_east() {
         let input = read_to_string(EXAMPLE).unwrap();
         let mut map = parse(&input);
-        roll_east(&mut map);
+        tilt(&mut map, Dir::East);
         let input = read_to_string("example-east.txt").unwrap();
         let expected = parse(&input);
         assert_eq!(map, expected);
@@ -204,15 +286,16 @@ mod tests {
     fn example_spin() {
         let input = read_to_string(EXAMPLE).unwrap();
         let mut map = parse(&input);
-        spin(&mut map);
+        let spin = "N W S E".parse().unwrap();
+        run(&mut map, &spin);
         let input = read_to_string("cycle1.txt").unwrap();
         let expected = parse(&input);
         assert_eq!(map, expected);
-        spin(&mut map);
+        run(&mut map, &spin);
         let input = read_to_string("cycle2.txt").unwrap();
         let expected = parse(&input);
         assert_eq!(map, expected);
-        spin(&mut map);
+        run(&mut map, &spin);
         let input = read_to_string("cycle3.txt").unwrap();
         let expected = parse(&input);
         assert_eq!(map, expected);
@@ -229,4 +312,101 @@ mod tests {
         let input = read_to_string(EXAMPLE).unwrap();
         assert_eq!(process2(&input), 64);
     }
+
+    #[test]
+    fn test_script() {
+        let script = "N W S E x1000000000; N E;S x3".parse::<Script>();
+        let steps = script.unwrap().steps;
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[0].tilts, DIRS);
+        assert_eq!(steps[0].times, 1000000000);
+        assert_eq!(steps[1].tilts, [Dir::North, Dir::East]);
+        assert_eq!(steps[1].times, 1);
+        assert_eq!(steps[2].tilts, [Dir::South]);
+        assert_eq!(steps[2].times, 3);
+
+        for (script, err) in [
+            ("", ScriptError::Empty),
+            ("N;", ScriptError::Empty),
+            ("x3", ScriptError::Empty),
+            ("N Q", ScriptError::Token("Q".to_string())),
+            ("N x3 E", ScriptError::Token("x3".to_string())),
+            ("N xx", ScriptError::Count("xx".to_string())),
+        ] {
+            assert_eq!(script.parse::<Script>(), Err(err), "{script}");
+        }
+    }
+
+    #[test]
+    fn test_cycles() {
+        let input = read_to_string(EXAMPLE).unwrap();
+        for tilts in [DIRS.to_vec(), vec![Dir::North, Dir::East]] {
+            let mut slow = parse(&input);
+            for times in 0..30 {
+                let mut map = parse(&input);
+                let step = Step {
+                    tilts: tilts.clone(),
+                    times,
+                };
+                perform(&mut map, &step);
+                assert_eq!(map, slow, "{times}");
+                for dir in &tilts {
+                    tilt(&mut slow, *dir);
+                }
+            }
+        }
+
+        let mut map = parse(&input);
+        run(&mut map, &"N W S E x1000000000".parse().unwrap());
+        assert_eq!(load(&map, Dir::North), 64);
+    }
+
+    #[test]
+    fn test_load() {
+        let map = parse("O.#\n..O");
+        assert_eq!(load(&map, Dir::North), 3);
+        assert_eq!(load(&map, Dir::South), 3);
+        assert_eq!(load(&map, Dir::West), 4);
+        assert_eq!(load(&map, Dir::East), 4);
+
+        let input = read_to_string(EXAMPLE).unwrap();
+        let mut map = parse(&input);
+        tilt(&mut map, Dir::East);
+        let rocks = map.iter().flatten().filter(|s| **s == Space::Round);
+        let w = map[0].len() + 1;
+        let total = load(&map, Dir::West) + load(&map, Dir::East);
+        assert_eq!(total, rocks.count() * w);
+    }
+
+    fn platform(rng: &mut Rng) -> String {
+        let (w, h) = (rng.range(1, 12), rng.range(1, 12));
+        text(&rng.grid(w, h, &[('.', 4), ('O', 2), ('#', 1)]))
+    }
+
+    fn tilted(map: &str, dir: Dir) -> Grid<Space> {
+        let mut map = parse(map);
+        tilt(&mut map, dir);
+        map
+    }
+
+    // tilting north and then turning the platform is the same as turning
+    // it and tilting toward wherever north ended up
+    fn check_turn(
+        cases: &[String], turn: fn(&[Vec<Space>]) -> Grid<Space>,
+        turn_text: fn(&[Vec<char>]) -> Grid<char>, dir: Dir,
+    ) {
+        let transform = |s: &str| each_grid(s, turn_text);
+        let before = |s: &str| turn(&tilted(s, Dir::North));
+        let after = |s: &str| tilted(s, dir);
+        check(cases, transform, before, after, |a, b| a == b);
+    }
+
+    #[test]
+    fn test_turned() {
+        let cases = cases(100, &[EXAMPLE], platform);
+        check_turn(&cases, rotate_ccw, rotate_ccw, Dir::West);
+        check_turn(&cases, rotate_cw, rotate_cw, Dir::East);
+        check_turn(&cases, flip_rows, flip_rows, Dir::South);
+        check_turn(&cases, flip_cols, flip_cols, Dir::North);
+    }
 }
@@ -1,65 +1,244 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::env;
+use std::fmt::{self, Display, Formatter};
 use std::fs::read_to_string;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
+
+// the puzzle's HASH; every step is mod 256, so u8 wrapping arithmetic does it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct HolidayHasher {
+    state: u8,
+}
+
+impl Hasher for HolidayHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.state = self.state.wrapping_add(*b).wrapping_mul(17);
+        }
+    }
+
+    // str's Hash ends every string with 0xff to keep ("ab", "c") and
+    // ("a", "bc") apart; drop it so a label hashes the way the puzzle says
+    fn write_u8(&mut self, i: u8) {
+        if i != 0xff {
+            self.write(&[i]);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.state.into()
+    }
+}
+
+// only 256 distinct hashes, so a HashMap built on it degrades quickly as
+// it grows; see the bench mode in main
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct HolidayBuildHasher;
+
+impl BuildHasher for HolidayBuildHasher {
+    type Hasher = HolidayHasher;
+
+    fn build_hasher(&self) -> HolidayHasher {
+        HolidayHasher::default()
+    }
+}
 
 fn hash_step(input: &str) -> usize {
-    input.bytes().fold(0, |x, y| {
-        ((((x as u16) + (y as u16)) * 17) % 256).try_into().unwrap()
-    })
+    let mut h = HolidayHasher::default();
+    h.write(input.as_bytes());
+    h.finish() as usize
 }
 
-fn process1(input: &String) -> usize {
-    input.trim().split(",").map(hash_step).sum()
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step<'a> {
+    Insert { label: &'a str, focal: usize },
+    Remove { label: &'a str },
+}
+
+// steps count from 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StepError {
+    NoOperation { step: usize },
+    EmptyLabel { step: usize },
+    BadFocal { step: usize },
+}
+
+impl<'a> Step<'a> {
+    fn parse(n: usize, s: &'a str) -> Result<Step<'a>, StepError> {
+        let step = if let Some((label, focal)) = s.split_once('=') {
+            let focal = match focal.parse() {
+                Ok(f @ 1..=9) => f,
+                _ => return Err(StepError::BadFocal { step: n }),
+            };
+            Step::Insert { label, focal }
+        } else if let Some(label) = s.strip_suffix('-') {
+            Step::Remove { label }
+        } else {
+            return Err(StepError::NoOperation { step: n });
+        };
+        if step.label().is_empty() {
+            return Err(StepError::EmptyLabel { step: n });
+        }
+        Ok(step)
+    }
+
+    fn label(&self) -> &'a str {
+        match self {
+            Step::Insert { label, .. } | Step::Remove { label } => label,
+        }
+    }
 }
 
-fn process2(input: &String) -> usize {
-    let input = input.trim().split(",");
-    let mut boxes: Vec<Vec<(&str, usize)>> = Vec::new();
-    for _ in 0..256 {
-        boxes.push(Vec::new());
+fn parse(input: &str) -> Result<Vec<Step<'_>>, StepError> {
+    input
+        .trim()
+        .split(',')
+        .enumerate()
+        .map(|(n, s)| Step::parse(n, s))
+        .collect()
+}
+
+// removing a lens leaves a hole in its box so that every other slot, and
+// the index pointing at it, stays put; a box is packed again once it is
+// mostly holes
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LensBoxes<'a> {
+    boxes: Vec<Vec<Option<(&'a str, usize)>>>,
+    index: HashMap<&'a str, usize>,
+}
+
+impl<'a> LensBoxes<'a> {
+    fn new() -> LensBoxes<'a> {
+        LensBoxes {
+            boxes: vec![Vec::new(); 256],
+            index: HashMap::new(),
+        }
     }
 
-    'outer: for cmd in input {
-        if cmd.contains("=") {
-            let mut cmd = cmd.split("=");
-            let k = cmd.next().unwrap();
-            let v = cmd.next().unwrap().parse::<usize>().unwrap();
-            let h = hash_step(k);
-            for lens in &mut boxes[h] {
-                if lens.0 == k {
-                    lens.1 = v;
-                    continue 'outer;
+    fn apply(&mut self, step: Step<'a>) {
+        let h = hash_step(step.label());
+        match step {
+            Step::Insert { label, focal } => match self.index.get(label) {
+                Some(&slot) => self.boxes[h][slot] = Some((label, focal)),
+                None => {
+                    self.index.insert(label, self.boxes[h].len());
+                    self.boxes[h].push(Some((label, focal)));
                 }
-            }
-            boxes[h].push((k, v));
-        } else if cmd.contains("-") {
-            let k = cmd.strip_suffix("-").unwrap();
-            let h = hash_step(k);
-            for i in 0..boxes[h].len() {
-                if boxes[h][i].0 == k {
-                    boxes[h].remove(i);
-                    break;
+            },
+            Step::Remove { label } => {
+                if let Some(slot) = self.index.remove(label) {
+                    self.boxes[h][slot] = None;
+                    if self.lenses(h).count() * 2 < self.boxes[h].len() {
+                        self.pack(h);
+                    }
                 }
             }
-        } else {
-            panic!("bad input");
         }
     }
 
-    let mut total = 0;
-    for i in 0..boxes.len() {
-        for j in 0..boxes[i].len() {
-            total += (i + 1) * (j + 1) * boxes[i][j].1;
+    fn pack(&mut self, h: usize) {
+        self.boxes[h].retain(|x| x.is_some());
+        for (slot, (label, _)) in self.boxes[h].iter().flatten().enumerate() {
+            self.index.insert(label, slot);
         }
     }
-    total
+
+    fn lenses(&self, h: usize) -> impl Iterator<Item = &(&'a str, usize)> {
+        self.boxes[h].iter().flatten()
+    }
+
+    fn focusing_power(&self) -> usize {
+        (0..self.boxes.len())
+            .flat_map(|h| {
+                self.lenses(h)
+                    .enumerate()
+                    .map(move |(slot, (_, focal))| (h + 1) * (slot + 1) * focal)
+            })
+            .sum()
+    }
+}
+
+// the non-empty boxes, one per line, as in the puzzle text
+impl Display for LensBoxes<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for h in 0..self.boxes.len() {
+            if self.lenses(h).next().is_none() {
+                continue;
+            }
+            write!(f, "Box {h}:")?;
+            for (label, focal) in self.lenses(h) {
+                write!(f, " [{label} {focal}]")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn trace(input: &str) -> Result<String, StepError> {
+    let mut boxes = LensBoxes::new();
+    let mut out = String::new();
+    for (s, step) in input.trim().split(',').zip(parse(input)?) {
+        boxes.apply(step);
+        out += &format!("After \"{s}\":\n{boxes}\n");
+    }
+    Ok(out)
+}
+
+fn process1(input: &str) -> usize {
+    input.trim().split(",").map(hash_step).sum()
+}
+
+fn process2(input: &str) -> Result<usize, StepError> {
+    let mut boxes = LensBoxes::new();
+    for step in parse(input)? {
+        boxes.apply(step);
+    }
+    Ok(boxes.focusing_power())
+}
+
+// insert every step into a map, then look each one up, `rounds` times
+fn bench<S: BuildHasher + Default>(steps: &[&str], rounds: usize) -> Duration {
+    let start = Instant::now();
+    for _ in 0..rounds {
+        let mut map = HashMap::with_hasher(S::default());
+        for (i, s) in steps.iter().enumerate() {
+            map.insert(*s, i);
+        }
+        let found = steps.iter().filter(|s| map.contains_key(*s)).count();
+        assert_eq!(found, steps.len());
+    }
+    start.elapsed()
 }
 
 fn main() {
     const INPUT: &str = "input.txt";
     let input = read_to_string(INPUT).unwrap();
+    if env::args().nth(1).as_deref() == Some("trace") {
+        match trace(&input) {
+            Ok(t) => print!("{t}"),
+            Err(e) => println!("{e:?}"),
+        }
+        return;
+    }
+    if env::args().nth(1).as_deref() == Some("bench") {
+        let steps = input.trim().split(',').collect::<Vec<_>>();
+        let holiday = bench::<HolidayBuildHasher>(&steps, 100);
+        let sip = bench::<RandomState>(&steps, 100);
+        println!("{} steps, 100 rounds", steps.len());
+        println!("holiday: {holiday:?}");
+        println!("siphash: {sip:?}");
+        return;
+    }
+
     let total = process1(&input);
     println!("1: {total}");
-    let total = process2(&input);
-    println!("2: {total}");
+    match process2(&input) {
+        Ok(total) => println!("2: {total}"),
+        Err(e) => println!("2: {e:?}"),
+    }
 }
 
 #[cfg(test)]
@@ -73,6 +252,29 @@ mod tests {
         assert_eq!(hash_step("rn=1"), 30);
     }
 
+    #[test]
+    fn test_hasher() {
+        let input = read_to_string(EXAMPLE).unwrap();
+        for step in input.trim().split(',') {
+            let label = step.split(['=', '-']).next().unwrap();
+            for key in [step, label] {
+                let h = HolidayBuildHasher.hash_one(key);
+                assert_eq!(h as usize, hash_step(key));
+                assert_eq!(HolidayBuildHasher.hash_one(key.to_string()), h);
+            }
+        }
+        assert_eq!(HolidayBuildHasher.hash_one("rn"), 0);
+        assert_eq!(HolidayBuildHasher.hash_one("qp"), 1);
+        assert_eq!(HolidayBuildHasher.hash_one("HASH"), 52);
+
+        let mut map = HashMap::with_hasher(HolidayBuildHasher);
+        map.insert("rn".to_string(), 1);
+        map.insert("cm".to_string(), 2);
+        assert_eq!(map.get("rn"), Some(&1));
+        assert_eq!(map.get("cm"), Some(&2));
+        assert_eq!(map.get("qp"), None);
+    }
+
     #[test]
     fn example1() {
         let input = read_to_string(EXAMPLE).unwrap();
@@ -82,6 +284,59 @@ mod tests {
     #[test]
     fn example2() {
         let input = read_to_string(EXAMPLE).unwrap();
-        assert_eq!(process2(&input), 145);
+        assert_eq!(process2(&input), Ok(145));
+    }
+
+    #[test]
+    fn test_trace() {
+        let input = read_to_string(EXAMPLE).unwrap();
+        let t = trace(&input).unwrap();
+        assert!(t.starts_with(
+            "After \"rn=1\":\n\
+             Box 0: [rn 1]\n\
+             \n\
+             After \"cm-\":\n\
+             Box 0: [rn 1]\n\
+             \n\
+             After \"qp=3\":\n\
+             Box 0: [rn 1]\n\
+             Box 1: [qp 3]\n\n"
+        ));
+        assert!(t.ends_with(
+            "After \"ot=7\":\n\
+             Box 0: [rn 1] [cm 2]\n\
+             Box 3: [ot 7] [ab 5] [pc 6]\n\n"
+        ));
+    }
+
+    #[test]
+    fn test_boxes() {
+        let mut boxes = LensBoxes::new();
+        for step in parse("rn=1,cm=2,qp=3,rn-,qp=4,rn=5,xx-").unwrap() {
+            boxes.apply(step);
+        }
+        assert_eq!(boxes.to_string(), "Box 0: [cm 2] [rn 5]\nBox 1: [qp 4]\n");
+        assert_eq!(boxes.focusing_power(), 2 + 2 * 5 + 2 * 4);
+
+        // ar, ba, ps and qb all land in box 19
+        let mut boxes = LensBoxes::new();
+        for step in parse("ar=1,ba=2,ps=3,qb=4,ar-,ba-,ps-").unwrap() {
+            boxes.apply(step);
+        }
+        assert_eq!(boxes.boxes[19], [Some(("qb", 4))]);
+        for step in parse("ar=5,qb=6").unwrap() {
+            boxes.apply(step);
+        }
+        assert_eq!(boxes.to_string(), "Box 19: [qb 6] [ar 5]\n");
+        assert_eq!(boxes.index, HashMap::from([("qb", 0), ("ar", 1)]));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse("rn=1,cm"), Err(StepError::NoOperation { step: 1 }));
+        assert_eq!(parse("=1"), Err(StepError::EmptyLabel { step: 0 }));
+        assert_eq!(parse("a-,b=x"), Err(StepError::BadFocal { step: 1 }));
+        assert_eq!(parse("a-,b=10"), Err(StepError::BadFocal { step: 1 }));
+        assert_eq!(process2("a=1,-"), Err(StepError::EmptyLabel { step: 1 }));
     }
 }
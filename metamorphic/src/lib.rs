@@ -0,0 +1,221 @@
+use records::records;
+use std::fmt::Debug;
+use std::fs::read_to_string;
+
+// metamorphic checks for the grid days: we rarely know the answer for an
+// arbitrary input, but we do know how it must change when the input is
+// turned, mirrored or padded, so check that over examples and generated
+// inputs alike
+
+pub type Grid<T> = Vec<Vec<T>>;
+
+pub fn transpose<T: Clone>(grid: &[Vec<T>]) -> Grid<T> {
+    let w = grid.first().map_or(0, |r| r.len());
+    (0..w)
+        .map(|x| grid.iter().map(|r| r[x].clone()).collect())
+        .collect()
+}
+
+// upside down
+pub fn flip_rows<T: Clone>(grid: &[Vec<T>]) -> Grid<T> {
+    grid.iter().rev().cloned().collect()
+}
+
+// left to right
+pub fn flip_cols<T: Clone>(grid: &[Vec<T>]) -> Grid<T> {
+    grid.iter()
+        .map(|r| r.iter().rev().cloned().collect())
+        .collect()
+}
+
+// a quarter turn clockwise, so the west edge becomes the north edge
+pub fn rotate_cw<T: Clone>(grid: &[Vec<T>]) -> Grid<T> {
+    flip_cols(&transpose(grid))
+}
+
+// a quarter turn counterclockwise, so the north edge becomes the west edge
+pub fn rotate_ccw<T: Clone>(grid: &[Vec<T>]) -> Grid<T> {
+    flip_rows(&transpose(grid))
+}
+
+// a row of `fill` before row `at`
+pub fn insert_row<T: Clone>(grid: &[Vec<T>], at: usize, fill: T) -> Grid<T> {
+    let w = grid.first().map_or(0, |r| r.len());
+    let mut grid = grid.to_vec();
+    grid.insert(at, vec![fill; w]);
+    grid
+}
+
+// a column of `fill` before column `at`
+pub fn insert_col<T: Clone>(grid: &[Vec<T>], at: usize, fill: T) -> Grid<T> {
+    let mut grid = grid.to_vec();
+    for row in &mut grid {
+        row.insert(at, fill.clone());
+    }
+    grid
+}
+
+pub fn text(grid: &[Vec<char>]) -> String {
+    grid.iter()
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+// applies `f` to each grid of an input holding one or more, separated by
+// blank lines
+pub fn each_grid<F>(input: &str, f: F) -> String
+where
+    F: Fn(&[Vec<char>]) -> Grid<char>,
+{
+    records(input.lines())
+        .map(|r| {
+            let grid =
+                r.iter().map(|l| l.chars().collect()).collect::<Vec<_>>();
+            text(&f(&grid))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// swaps each pair of tiles, for those whose shape changes under a transform
+pub fn swap_chars(input: &str, pairs: &[(char, char)]) -> String {
+    input
+        .chars()
+        .map(|c| {
+            let swap = |&(a, b): &(char, char)| {
+                (c == a).then_some(b).or((c == b).then_some(a))
+            };
+            pairs.iter().find_map(swap).unwrap_or(c)
+        })
+        .collect()
+}
+
+// splitmix64, so a failing case comes back from its seed
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // in lo..hi
+    pub fn range(&mut self, lo: usize, hi: usize) -> usize {
+        lo + (self.next_u64() % (hi - lo) as u64) as usize
+    }
+
+    // a w by h grid of tiles drawn in proportion to their weights
+    pub fn grid(
+        &mut self, w: usize, h: usize, tiles: &[(char, usize)],
+    ) -> Grid<char> {
+        let total = tiles.iter().map(|t| t.1).sum();
+        let mut tile = || {
+            let mut n = self.range(0, total);
+            for (c, weight) in tiles {
+                if n < *weight {
+                    return *c;
+                }
+                n -= weight;
+            }
+            unreachable!()
+        };
+        (0..h).map(|_| (0..w).map(|_| tile()).collect()).collect()
+    }
+}
+
+// `count` inputs from `make`, seeded 0, 1, 2 and on
+pub fn generate<F>(count: u64, make: F) -> Vec<String>
+where
+    F: Fn(&mut Rng) -> String,
+{
+    (0..count).map(|seed| make(&mut Rng::new(seed))).collect()
+}
+
+// `count` generated inputs and then the named example files
+pub fn cases<F>(count: u64, examples: &[&str], make: F) -> Vec<String>
+where
+    F: Fn(&mut Rng) -> String,
+{
+    let mut cases = generate(count, make);
+    cases.extend(examples.iter().map(|f| read_to_string(f).unwrap()));
+    cases
+}
+
+// solves each case with `before` and its transform with `after`, and
+// panics with both inputs if `holds` rejects the pair of answers
+pub fn check<A, B>(
+    cases: &[String], transform: impl Fn(&str) -> String,
+    before: impl Fn(&str) -> A, after: impl Fn(&str) -> B,
+    holds: impl Fn(&A, &B) -> bool,
+) where
+    A: Debug,
+    B: Debug,
+{
+    for (i, input) in cases.iter().enumerate() {
+        let changed = transform(input);
+        let (a, b) = (before(input), after(&changed));
+        assert!(
+            holds(&a, &b),
+            "case {i}: {a:?} then {b:?}\n{input}\nbecame\n{changed}"
+        );
+    }
+}
+
+// the answer doesn't change under the transform
+pub fn invariant<A>(
+    cases: &[String], transform: impl Fn(&str) -> String,
+    solve: impl Fn(&str) -> A,
+) where
+    A: Debug + PartialEq,
+{
+    check(cases, transform, &solve, &solve, |a, b| a == b);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transforms() {
+        let g = vec![vec![1, 2, 3], vec![4, 5, 6]];
+        assert_eq!(transpose(&g), [[1, 4], [2, 5], [3, 6]]);
+        assert_eq!(rotate_cw(&g), [[4, 1], [5, 2], [6, 3]]);
+        assert_eq!(rotate_ccw(&g), [[3, 6], [2, 5], [1, 4]]);
+        assert_eq!(flip_rows(&g), [[4, 5, 6], [1, 2, 3]]);
+        assert_eq!(flip_cols(&g), [[3, 2, 1], [6, 5, 4]]);
+        assert_eq!(rotate_ccw(&rotate_cw(&g)), g);
+        let turned = (0..4).fold(g.clone(), |g, _| rotate_cw(&g));
+        assert_eq!(turned, g);
+        assert_eq!(insert_row(&g, 1, 0), [[1, 2, 3], [0, 0, 0], [4, 5, 6]]);
+        assert_eq!(insert_col(&g, 3, 0), [[1, 2, 3, 0], [4, 5, 6, 0]]);
+    }
+
+    #[test]
+    fn test_text() {
+        let input = "ab\ncd\n\nef\n";
+        assert_eq!(each_grid(input, transpose), "ac\nbd\n\ne\nf\n");
+        assert_eq!(each_grid(input, |g| g.to_vec()), input);
+        assert_eq!(swap_chars("F-7|L", &[('F', '7'), ('L', 'J')]), "7-F|J");
+    }
+
+    #[test]
+    fn test_rng() {
+        let a = generate(3, |r| text(&r.grid(4, 3, &[('.', 3), ('#', 1)])));
+        let b = generate(3, |r| text(&r.grid(4, 3, &[('.', 3), ('#', 1)])));
+        assert_eq!(a, b);
+        assert_ne!(a[0], a[1]);
+        assert!(a.iter().all(|g| g.lines().all(|l| l.len() == 4)));
+        let c = cases(3, &[], |r| text(&r.grid(4, 3, &[('.', 3), ('#', 1)])));
+        assert_eq!(c, a);
+        let mut r = Rng::new(7);
+        assert!((0..100).all(|_| (3..5).contains(&r.range(3, 5))));
+    }
+}
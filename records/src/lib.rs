@@ -0,0 +1,65 @@
+// groups lines into records separated by one or more blank lines, skipping
+// blank lines at either end.  str::lines and BufRead::lines both drop the
+// \r of a \r\n ending; a line holding only whitespace counts as blank
+pub struct Records<I> {
+    lines: I,
+}
+
+pub fn records<I, S>(lines: I) -> Records<I::IntoIter>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    Records {
+        lines: lines.into_iter(),
+    }
+}
+
+impl<I, S> Iterator for Records<I>
+where
+    I: Iterator<Item = S>,
+    S: AsRef<str>,
+{
+    type Item = Vec<S>;
+
+    fn next(&mut self) -> Option<Vec<S>> {
+        let mut record = Vec::new();
+        for line in self.lines.by_ref() {
+            if !line.as_ref().trim().is_empty() {
+                record.push(line);
+            } else if !record.is_empty() {
+                break;
+            }
+        }
+        (!record.is_empty()).then_some(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(input: &str) -> Vec<Vec<&str>> {
+        records(input.lines()).collect()
+    }
+
+    #[test]
+    fn test_records() {
+        let want = [vec!["1", "2"], vec!["3"]];
+        assert_eq!(split("1\n2\n\n3"), want);
+        assert_eq!(split("1\n2\n\n3\n"), want);
+        assert_eq!(split("1\n2\n\n3\n\n"), want);
+        assert_eq!(split("\n\n1\n2\n\n\n\n3\n\n\n"), want);
+        assert_eq!(split("1\r\n2\r\n\r\n3\r\n"), want);
+        assert_eq!(split("1\n2\n  \n\t\n3"), want);
+        assert_eq!(split(""), Vec::<Vec<&str>>::new());
+        assert_eq!(split("\n\n"), Vec::<Vec<&str>>::new());
+    }
+
+    #[test]
+    fn test_owned() {
+        let lines = "a\n\nb\nc".lines().map(String::from);
+        let r = records(lines).collect::<Vec<_>>();
+        assert_eq!(r, [vec!["a"], vec!["b", "c"]]);
+    }
+}
@@ -0,0 +1,121 @@
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::env;
+use std::fs::{read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Report {
+    day: String,
+    ok: bool,
+    output: String,
+}
+
+fn is_day(path: &Path) -> bool {
+    path.join("Cargo.toml").is_file()
+}
+
+// every YYYY/DD directory with a manifest, in order
+fn find_days(root: &Path) -> Vec<String> {
+    let mut days = Vec::new();
+    for year in read_dir(root).unwrap().flatten() {
+        let year = year.file_name().into_string().unwrap();
+        if year.len() != 4 || !year.bytes().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        for day in read_dir(root.join(&year)).unwrap().flatten() {
+            if is_day(&day.path()) {
+                let day = day.file_name().into_string().unwrap();
+                days.push(format!("{year}/{day}"));
+            }
+        }
+    }
+    days.sort();
+    days
+}
+
+fn has_parallel(dir: &Path) -> bool {
+    read_to_string(dir.join("Cargo.toml"))
+        .map(|m| m.lines().any(|l| l.starts_with("parallel =")))
+        .unwrap_or(false)
+}
+
+fn run_day(root: &Path, day: &str) -> Report {
+    let dir = root.join(day);
+    let mut cmd = Command::new(env!("CARGO"));
+    cmd.args(["run", "--release", "-q"]).current_dir(&dir);
+    if cfg!(feature = "parallel") && has_parallel(&dir) {
+        cmd.args(["--features", "parallel"]);
+    }
+
+    let (ok, output) = match cmd.output() {
+        Ok(out) => {
+            let mut text = String::from_utf8_lossy(&out.stdout).into_owned();
+            if !out.status.success() {
+                text += &String::from_utf8_lossy(&out.stderr);
+            }
+            (out.status.success(), text)
+        }
+        Err(e) => (false, format!("can't run cargo: {e}\n")),
+    };
+    Report {
+        day: day.to_string(),
+        ok,
+        output,
+    }
+}
+
+fn run_days(root: &Path, days: &[String]) -> Vec<Report> {
+    #[cfg(feature = "parallel")]
+    let days = days.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let days = days.iter();
+    days.map(|d| run_day(root, d)).collect()
+}
+
+fn main() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+    let mut days = env::args().skip(1).collect::<Vec<_>>();
+    if days.is_empty() {
+        days = find_days(&root);
+    }
+
+    let mut failed = 0;
+    for r in run_days(&root, &days) {
+        let status = if r.ok { "" } else { " (failed)" };
+        println!("== {}{status}", r.day);
+        print!("{}", r.output);
+        if !r.ok {
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..")
+    }
+
+    #[test]
+    fn test_find_days() {
+        let days = find_days(&root());
+        assert!(days.contains(&"2022/01".to_string()));
+        assert!(days.contains(&"2023/15".to_string()));
+        assert!(days.windows(2).all(|w| w[0] < w[1]));
+        assert!(!days.contains(&"runner".to_string()));
+    }
+
+    #[test]
+    fn test_has_parallel() {
+        assert!(has_parallel(&root().join("2023/13")));
+        assert!(!has_parallel(&root().join("2023/14")));
+        assert!(!has_parallel(&root().join("2023/15")));
+    }
+}